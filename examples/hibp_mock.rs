@@ -0,0 +1,84 @@
+// Local stand-in for the Have I Been Pwned range API, to try the breach check
+// without network access. Serves GET /range/{prefix} from a HASH:COUNT file.
+//
+//   cargo run --example hibp_mock -- hashes.txt [port]
+//
+// Then set "hibp_api_url": "http://127.0.0.1:8000" in settings.json.
+
+use std::collections::BTreeMap;
+use std::{env, fs, process};
+use tiny_http::{Header, Response, Server};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let file = args.next().unwrap_or_else(|| {
+        eprintln!("Usage: hibp_mock <hash file> [port]");
+        process::exit(2);
+    });
+    let port = args.next().unwrap_or_else(|| "8000".to_string());
+
+    let hashes = parse(&fs::read_to_string(&file).unwrap_or_else(|e| {
+        eprintln!("Error reading {}: {}", file, e);
+        process::exit(1);
+    }));
+
+    let server = Server::http(format!("127.0.0.1:{}", port)).unwrap_or_else(|e| {
+        eprintln!("Error starting the server: {}", e);
+        process::exit(1);
+    });
+    println!(
+        "Serving {} hashes on http://127.0.0.1:{}",
+        hashes.len(),
+        port
+    );
+    serve(server, &hashes);
+}
+
+// Hash to count, lines without a count are seen once
+pub fn parse(content: &str) -> BTreeMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (hash, count) = line.split_once(':').unwrap_or((line, "1"));
+            let hash = hash.trim().to_uppercase();
+            (hash.len() == 40).then(|| (hash, count.trim().parse().unwrap_or(1)))
+        })
+        .collect()
+}
+
+// Answer range requests as long as the process runs, also used by the breach tests
+pub fn serve(server: Server, hashes: &BTreeMap<String, u64>) {
+    for request in server.incoming_requests() {
+        let prefix = request
+            .url()
+            .strip_prefix("/range/")
+            .map(|prefix| prefix.to_uppercase())
+            .filter(|prefix| prefix.len() == 5 && prefix.chars().all(|c| c.is_ascii_hexdigit()));
+        let padding = request
+            .headers()
+            .iter()
+            .any(|header| header.field.equiv("Add-Padding") && header.value == "true");
+
+        let response = match prefix {
+            Some(prefix) => {
+                let mut body: Vec<String> = hashes
+                    .range(prefix.clone()..)
+                    .take_while(|(hash, _)| hash.starts_with(&prefix))
+                    .map(|(hash, count)| format!("{}:{}", &hash[5..], count))
+                    .collect();
+                // Padding lines have a count of 0 like on the real API
+                if padding {
+                    body.extend((0..10).map(|i| format!("{:035X}:0", i)));
+                }
+                Response::from_string(body.join("\r\n"))
+                    .with_header(Header::from_bytes("Content-Type", "text/plain").unwrap())
+            }
+            None => Response::from_string("The hash prefix was not in a valid format")
+                .with_status_code(400),
+        };
+        println!("{} {}", request.method(), request.url());
+        if let Err(e) = request.respond(response) {
+            eprintln!("Error responding: {}", e);
+        }
+    }
+}
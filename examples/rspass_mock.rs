@@ -0,0 +1,275 @@
+// Local stand-in for an rsPass backend, to try PassHub without a server.
+// Accounts and vaults are kept in memory, or in a JSON file with --state.
+//
+//   cargo run --example rspass_mock -- [port] [--state state.json] [--no-prelogin] [--token-seconds N]
+//
+// Then enter http://127.0.0.1:8080 as backend URL. --no-prelogin behaves like a
+// backend without per-account salts; accounts registered that way are moved to a
+// random salt on their next login once the mock runs without it.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+#[derive(Default, Serialize, Deserialize)]
+struct Account {
+    password_hash: String,
+    kdf: Option<Value>, // None for accounts salted with the e-mail
+    vault: Option<String>,
+    revision: u64,
+}
+
+pub struct Backend {
+    accounts: HashMap<String, Account>,
+    sessions: HashMap<String, (String, u64)>, // Token to e-mail and expiry
+    state: Option<String>,
+    prelogin: bool,
+    token_seconds: u64,
+}
+
+type Reply = Response<std::io::Cursor<Vec<u8>>>;
+
+fn main() {
+    let (mut state, mut prelogin, mut token_seconds) = (None, true, 3600);
+    let mut port = "8080".to_string();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--state" => state = args.next(),
+            "--no-prelogin" => prelogin = false,
+            "--token-seconds" => {
+                token_seconds = args.next().and_then(|s| s.parse().ok()).unwrap_or(3600)
+            }
+            _ if arg.parse::<u16>().is_ok() => port = arg,
+            _ => {
+                eprintln!(
+                    "Usage: rspass_mock [port] [--state file] [--no-prelogin] [--token-seconds N]"
+                );
+                process::exit(2);
+            }
+        }
+    }
+    let backend = Backend::new(state, prelogin, token_seconds);
+
+    let server = Server::http(format!("127.0.0.1:{}", port)).unwrap_or_else(|e| {
+        eprintln!("Error starting the server: {}", e);
+        process::exit(1);
+    });
+    println!("rsPass mock on http://127.0.0.1:{}", port);
+    serve(server, backend);
+}
+
+// Answer requests as long as the process runs, also used by the login tests
+pub fn serve(server: Server, mut backend: Backend) {
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        _ = request.as_reader().read_to_string(&mut body);
+        let body: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+
+        let response = backend.handle(&request, &body);
+        println!(
+            "{} {} -> {}",
+            request.method(),
+            request.url(),
+            response.status_code().0
+        );
+        backend.save();
+        if let Err(e) = request.respond(response) {
+            eprintln!("Error responding: {}", e);
+        }
+    }
+}
+
+impl Backend {
+    // Accounts are read from the state file if it exists
+    pub fn new(state: Option<String>, prelogin: bool, token_seconds: u64) -> Self {
+        let mut accounts = HashMap::new();
+        if let Some(state) = &state {
+            if let Ok(content) = fs::read_to_string(state) {
+                accounts = serde_json::from_str(&content).unwrap_or_else(|e| {
+                    eprintln!("Error reading {}: {}", state, e);
+                    process::exit(1);
+                });
+            }
+        }
+        Backend {
+            accounts,
+            sessions: HashMap::new(),
+            state,
+            prelogin,
+            token_seconds,
+        }
+    }
+
+    fn handle(&mut self, request: &Request, body: &Value) -> Reply {
+        let email = body["email"].as_str().unwrap_or_default().to_string();
+        match (request.method(), request.url()) {
+            (Method::Get, "/api/v1/health") => text(200, "OK"),
+            (Method::Post, "/api/v1/auth/prelogin") if self.prelogin => {
+                // Unknown and old accounts get the e-mail salted defaults
+                let kdf = self.accounts.get(&email).and_then(|a| a.kdf.clone());
+                reply(
+                    200,
+                    kdf.unwrap_or_else(|| {
+                        json!({ "kdf": "argon2id", "memory": 65536, "iterations": 3, "parallelism": 4, "salt": null })
+                    }),
+                )
+            }
+            (Method::Post, "/api/v1/auth/prelogin") => text(405, "Method not allowed"),
+            (Method::Post, "/api/v1/auth/register") => {
+                if self.accounts.contains_key(&email) {
+                    return text(409, "The user already exists");
+                }
+                let account = Account {
+                    password_hash: body["password_hash"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    kdf: body.get("kdf").cloned(),
+                    ..Account::default()
+                };
+                self.accounts.insert(email.clone(), account);
+                self.token(&email)
+            }
+            (Method::Post, "/api/v1/auth/login") => match self.accounts.get(&email) {
+                None => text(404, "User not found"),
+                Some(account)
+                    if Some(account.password_hash.as_str()) != body["password_hash"].as_str() =>
+                {
+                    text(401, "Wrong password")
+                }
+                Some(_) => self.token(&email),
+            },
+            (method, url) => {
+                let email = match self.session(request) {
+                    Some(email) => email,
+                    None => return text(401, "The token is invalid or expired"),
+                };
+                self.handle_account(method, url, &email, request, body)
+            }
+        }
+    }
+
+    fn handle_account(
+        &mut self,
+        method: &Method,
+        url: &str,
+        email: &str,
+        request: &Request,
+        body: &Value,
+    ) -> Reply {
+        let account = match self.accounts.get_mut(email) {
+            Some(account) => account,
+            None => return text(404, "User not found"),
+        };
+        match (method, url) {
+            (Method::Get, "/api/v1/sync/fetch") => match &account.vault {
+                Some(vault) => reply(
+                    200,
+                    json!({ "encrypted_data": vault, "revision": account.revision }),
+                )
+                .with_header(etag(account.revision)),
+                None => reply(200, Value::Null),
+            },
+            // Reject saves based on an older revision, like a backend with revisions does
+            (Method::Post, "/api/v1/sync/update" | "/api/v1/account/changepassword")
+                if outdated(request, account.revision) =>
+            {
+                text(412, "The vault was changed in the meantime")
+            }
+            (Method::Post, "/api/v1/sync/update") => {
+                account.vault = body["encrypted_data"].as_str().map(str::to_string);
+                account.revision += 1;
+                reply(200, json!({ "revision": account.revision }))
+                    .with_header(etag(account.revision))
+            }
+            (Method::Post, "/api/v1/account/changepassword") => {
+                account.password_hash = body["password_hash"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                account.vault = body["encrypted_data"].as_str().map(str::to_string);
+                account.kdf = body.get("kdf").cloned();
+                account.revision += 1;
+                text(200, "OK")
+            }
+            (Method::Get, "/api/v1/account/logout") => {
+                self.sessions.retain(|_, (session, _)| session != email);
+                text(200, "OK")
+            }
+            (Method::Get, "/api/v1/account/delete") => {
+                self.accounts.remove(email);
+                self.sessions.retain(|_, (session, _)| session != email);
+                text(200, "OK")
+            }
+            _ => text(404, "Not found"),
+        }
+    }
+
+    // Unsigned JWT with the expiry PassHub reads
+    fn token(&mut self, email: &str) -> Reply {
+        let exp = now() + self.token_seconds;
+        let token = format!(
+            "{}.{}.mock",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"none","typ":"JWT"}"#),
+            URL_SAFE_NO_PAD.encode(json!({ "sub": email, "exp": exp }).to_string())
+        );
+        self.sessions
+            .insert(token.clone(), (email.to_string(), exp));
+        reply(200, json!({ "token": token }))
+    }
+
+    fn session(&self, request: &Request) -> Option<String> {
+        let header = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))?;
+        let token = header.value.as_str().strip_prefix("Bearer ")?;
+        let (email, exp) = self.sessions.get(token)?;
+        (*exp > now()).then(|| email.clone())
+    }
+
+    fn save(&self) {
+        if let Some(state) = &self.state {
+            if let Err(e) = fs::write(state, serde_json::to_string_pretty(&self.accounts).unwrap())
+            {
+                eprintln!("Error writing {}: {}", state, e);
+            }
+        }
+    }
+}
+
+// If-Match names another revision than the current one
+fn outdated(request: &Request, revision: u64) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("If-Match"))
+        .is_some_and(|header| header.value.as_str().trim_matches('"') != revision.to_string())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn etag(revision: u64) -> Header {
+    Header::from_bytes("ETag", format!("\"{}\"", revision)).unwrap()
+}
+
+fn reply(status: u16, body: Value) -> Reply {
+    Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+}
+
+fn text(status: u16, body: &str) -> Reply {
+    Response::from_string(body).with_status_code(status)
+}
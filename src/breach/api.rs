@@ -0,0 +1,11 @@
+use crate::client::RsPassClient;
+use crate::error::Result;
+
+// Fetch all hash suffixes starting with the 5 character prefix from a
+// k-anonymity range API, the full hash never leaves the device
+pub fn fetch_range(client: &RsPassClient, prefix: &str) -> Result<String> {
+    let request = client
+        .get(&format!("/range/{}", prefix))
+        .header("Add-Padding", "true"); // Hides how many suffixes the prefix really has
+    Ok(client.send(request)?.text()?)
+}
@@ -0,0 +1,301 @@
+use super::api;
+use crate::client::RsPassClient;
+use crate::error::{PassHubError, Result};
+use crate::menue::logik::{Entry, ItemType};
+use crate::settings;
+use lazy_static::lazy_static;
+use log::debug;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Passwords are checked against Have I Been Pwned data by their SHA-1 hash.
+// The check runs in the background, the list shows results as they come in.
+
+// Where compromised hashes are looked up, a local file wins over the API
+pub enum BreachSource {
+    File(PathBuf),          // Downloaded HIBP file sorted by hash, one HASH:COUNT per line
+    RangeApi(RsPassClient), // Client for a range API like https://api.pwnedpasswords.com
+}
+
+lazy_static! {
+    // How often each hash was seen in breaches, 0 if never
+    static ref BREACHES: Mutex<HashMap<String, u64>> = Mutex::new(HashMap::new());
+    // Built on the first check and shared by all later ones
+    static ref SOURCE: Option<Arc<BreachSource>> =
+        BreachSource::from_settings(&settings::logik::load()).map(Arc::new);
+}
+static CHECKING: AtomicBool = AtomicBool::new(false);
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+impl BreachSource {
+    pub fn from_settings(settings: &settings::logik::Settings) -> Option<BreachSource> {
+        match (&settings.hibp_file, &settings.hibp_api_url) {
+            (Some(file), _) => Some(BreachSource::File(file.clone())),
+            // Timeouts and proxy apply, the CAs and pin trusted for the backend do not
+            (None, Some(url)) => match RsPassClient::with_settings(
+                url,
+                &settings::logik::Settings {
+                    ca_bundle: None,
+                    pinned_certificate: None,
+                    ..settings.clone()
+                },
+            ) {
+                Ok(client) => Some(BreachSource::RangeApi(client)),
+                Err(e) => {
+                    debug!("Breach: Error creating the API client: {}", e);
+                    None
+                }
+            },
+            (None, None) => None,
+        }
+    }
+
+    // Look up hashes that share the same 5 character prefix
+    fn lookup(&self, hashes: &[String]) -> Result<Vec<(String, u64)>> {
+        match self {
+            BreachSource::File(path) => {
+                // hibp_file is a setting, an unreadable file is a configuration error
+                let unreadable =
+                    |e: io::Error| PassHubError::Config(format!("{}: {}", path.display(), e));
+                let file = File::open(path).map_err(unreadable)?;
+                let size = file.metadata().map_err(unreadable)?.len();
+                let mut reader = BufReader::new(file);
+                hashes
+                    .iter()
+                    .map(|hash| {
+                        let count = search_file(&mut reader, size, hash).map_err(unreadable)?;
+                        Ok((hash.clone(), count))
+                    })
+                    .collect()
+            }
+            BreachSource::RangeApi(client) => {
+                let range = api::fetch_range(client, &hashes[0][..5])?;
+                let counts = parse_range(&range);
+                Ok(hashes
+                    .iter()
+                    .map(|hash| (hash.clone(), counts.get(&hash[5..]).copied().unwrap_or(0)))
+                    .collect())
+            }
+        }
+    }
+}
+
+// Start checking the login passwords that have not been checked yet
+pub fn check_entries(entries: &[Entry]) {
+    let source = match SOURCE.as_ref() {
+        Some(source) => Arc::clone(source),
+        None => return,
+    };
+
+    let mut hashes: Vec<String> = {
+        let known = BREACHES.lock().unwrap();
+        entries
+            .iter()
+            .filter(|entry| entry.item_type == ItemType::Login && !entry.login.password.is_empty())
+            .map(|entry| sha1_hex(&entry.login.password))
+            .filter(|hash| !known.contains_key(hash))
+            .collect()
+    };
+    hashes.sort();
+    hashes.dedup();
+    // A running check picks up the rest the next time the list is shown
+    if hashes.is_empty() || CHECKING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let generation = GENERATION.load(Ordering::SeqCst);
+    thread::spawn(move || {
+        for group in hashes.chunk_by(|a, b| a[..5] == b[..5]) {
+            match source.lookup(group) {
+                Ok(counts) => {
+                    let mut breaches = BREACHES.lock().unwrap();
+                    if GENERATION.load(Ordering::SeqCst) != generation {
+                        break; // The vault was locked in the meantime
+                    }
+                    breaches.extend(counts);
+                }
+                Err(e) => debug!("Breach: Error checking passwords: {}", e),
+            }
+        }
+        CHECKING.store(false, Ordering::SeqCst);
+    });
+}
+
+// How often the password was seen in breaches, None if it was not checked yet
+pub fn breach_count(password: &str) -> Option<u64> {
+    BREACHES.lock().unwrap().get(&sha1_hex(password)).copied()
+}
+
+// Drop all results when the vault is locked or closed
+pub fn forget() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    BREACHES.lock().unwrap().clear();
+}
+
+fn sha1_hex(password: &str) -> String {
+    hex::encode_upper(Sha1::digest(password.as_bytes()))
+}
+
+// Binary search over byte offsets, each step reads the first line starting at or after the offset
+fn search_file(reader: &mut BufReader<File>, size: u64, hash: &str) -> io::Result<u64> {
+    let (mut low, mut high) = (0, size);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match line_at(reader, mid)? {
+            Some(line) if line_hash(&line).as_str() < hash => low = mid + 1,
+            _ => high = mid,
+        }
+    }
+    Ok(match line_at(reader, low)? {
+        Some(line) if line_hash(&line) == hash => line_count(&line),
+        _ => 0,
+    })
+}
+
+fn line_at(reader: &mut BufReader<File>, offset: u64) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if offset > 0 {
+        // Skip the rest of the line the offset points into
+        reader.seek(SeekFrom::Start(offset - 1))?;
+        reader.read_line(&mut line)?;
+        line.clear();
+    } else {
+        reader.seek(SeekFrom::Start(0))?;
+    }
+    Ok((reader.read_line(&mut line)? > 0).then_some(line))
+}
+
+// Files may use lower or upper case hex, both sort the same way
+fn line_hash(line: &str) -> String {
+    line.split(':')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_uppercase()
+}
+
+// Lists without counts only hold compromised hashes
+fn line_count(line: &str) -> u64 {
+    line.split_once(':')
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(1)
+}
+
+// Range responses have one SUFFIX:COUNT per line, padding lines have a count of 0
+fn parse_range(range: &str) -> HashMap<String, u64> {
+    range
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter_map(|(suffix, count)| {
+            Some((suffix.trim().to_uppercase(), count.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+// The breach tests query the mock range API from the examples
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../../examples/hibp_mock.rs"]
+mod hibp_mock;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tiny_http::Server;
+
+    const FIRST: &str = "000000005AD76BD555C1D6D771DE417A4B87E4B4";
+    const MIDDLE: &str = "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"; // "password"
+    const LAST: &str = "FFFFFFFFF0F3D0B20F7E1D9D1A5F8B64D2B1E9C7";
+
+    fn search(content: &str, hash: &str) -> u64 {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let size = content.len() as u64;
+        search_file(&mut BufReader::new(file.reopen().unwrap()), size, hash).unwrap()
+    }
+
+    fn hash_file(line_end: &str) -> String {
+        [FIRST, MIDDLE, LAST]
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| format!("{}:{}{}", hash, i + 1, line_end))
+            .collect()
+    }
+
+    #[test]
+    fn search_file_finds_first_middle_and_last_line() {
+        let content = hash_file("\n");
+        assert_eq!(search(&content, FIRST), 1);
+        assert_eq!(search(&content, MIDDLE), 2);
+        assert_eq!(search(&content, LAST), 3);
+    }
+
+    #[test]
+    fn search_file_without_trailing_newline() {
+        let content = hash_file("\n");
+        assert_eq!(search(content.trim_end(), LAST), 3);
+    }
+
+    #[test]
+    fn search_file_misses_unknown_hashes() {
+        let content = hash_file("\n");
+        assert_eq!(search(&content, &"0".repeat(40)), 0);
+        assert_eq!(
+            search(&content, "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD9"),
+            0
+        );
+        assert_eq!(search(&content, &"F".repeat(40)), 0);
+        assert_eq!(search("", MIDDLE), 0);
+    }
+
+    #[test]
+    fn search_file_with_crlf_lines() {
+        let content = hash_file("\r\n");
+        assert_eq!(search(&content, FIRST), 1);
+        assert_eq!(search(&content, MIDDLE), 2);
+        assert_eq!(search(&content, LAST), 3);
+    }
+
+    #[test]
+    fn search_file_with_lowercase_hashes_and_without_counts() {
+        let content = format!(
+            "{}\n{}\n{}\n",
+            FIRST.to_lowercase(),
+            MIDDLE.to_lowercase(),
+            LAST.to_lowercase()
+        );
+        assert_eq!(search(&content, FIRST), 1);
+        assert_eq!(search(&content, MIDDLE), 1);
+        assert_eq!(search(&content, LAST), 1);
+    }
+
+    #[test]
+    fn parse_range_reads_counts_and_padding() {
+        let counts = parse_range("1e4c9b93f3f0682250b6cf8331b7ee68fd8:42\r\nABC:0\r\nbroken\r\n");
+        assert_eq!(counts.get("1E4C9B93F3F0682250B6CF8331B7EE68FD8"), Some(&42));
+        assert_eq!(counts.get("ABC"), Some(&0));
+        assert_eq!(counts.len(), 2);
+    }
+
+    #[test]
+    fn range_api_against_the_mock() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let hashes = super::hibp_mock::parse(&hash_file("\n"));
+        thread::spawn(move || super::hibp_mock::serve(server, &hashes));
+
+        let url = format!("http://127.0.0.1:{}", port);
+        let client = RsPassClient::with_settings(&url, &settings::logik::Settings::default());
+        let source = BreachSource::RangeApi(client.unwrap());
+        let other = "5BAA6FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF".to_string();
+        let counts = source.lookup(&[MIDDLE.to_string(), other.clone()]).unwrap();
+        assert_eq!(counts, vec![(MIDDLE.to_string(), 2), (other, 0)]);
+    }
+}
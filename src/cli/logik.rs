@@ -0,0 +1,657 @@
+use super::view;
+use crate::client::RsPassClient;
+use crate::error::PassHubError;
+use crate::export::logik::{self as export, ExportFormat};
+use crate::login::logik::{self as login, LoginError};
+use crate::menue::{
+    api::{self, Revision},
+    logik::{self as menue, Entry, FolderFilter, ItemType, Login, Uri},
+};
+use crate::register::logik::validate_password;
+use crate::sync::logik as sync;
+use crate::totp::logik::Totp;
+use crate::url_check::{self, logik as url_storage};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+// Exit codes, 2 is used by clap for usage errors
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_AUTH: i32 = 3;
+pub const EXIT_NOT_FOUND: i32 = 4;
+pub const EXIT_BACKEND: i32 = 5;
+
+/// PassHub for rsPass. Starts the interactive interface when no command is given.
+#[derive(Parser, Debug)]
+#[command(name = "passhub", version)]
+pub struct Cli {
+    /// URL of the rsPass backend (defaults to the stored one)
+    #[arg(long, global = true, env = "PASSHUB_URL")]
+    url: Option<String>,
+
+    /// Account e-mail (defaults to the stored one)
+    #[arg(long, global = true, env = "PASSHUB_EMAIL")]
+    email: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Verify the credentials and remember backend URL and e-mail
+    Login,
+    /// List all entries
+    List {
+        /// Only entries in this folder or its subfolders
+        #[arg(long)]
+        folder: Option<String>,
+        /// Only entries with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Only favorites
+        #[arg(long)]
+        favorites: bool,
+    },
+    /// Print a single field of an entry
+    Get {
+        /// Name or id of the entry
+        name: String,
+        #[arg(long, value_enum, default_value_t = Field::Password)]
+        field: Field,
+    },
+    /// Add a new entry, its password is read from PASSHUB_ENTRY_PASSWORD or prompted for
+    Add {
+        name: String,
+        #[arg(long)]
+        username: Option<String>,
+        /// Can be given several times
+        #[arg(long)]
+        uri: Vec<String>,
+        #[arg(long)]
+        notes: Option<String>,
+        /// otpauth:// URI or Base32 secret
+        #[arg(long)]
+        totp: Option<String>,
+        /// Subfolders are separated by "/"
+        #[arg(long)]
+        folder: Option<String>,
+        /// Can be given several times
+        #[arg(long)]
+        tag: Vec<String>,
+    },
+    /// Remove an entry
+    Rm {
+        /// Name or id of the entry
+        name: String,
+    },
+    /// Export the vault to a file
+    Export {
+        /// File to write, must not exist yet
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportKind::Encrypted)]
+        format: ExportKind,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportKind {
+    /// Protected by an export password (PASSHUB_EXPORT_PASSWORD or prompt)
+    Encrypted,
+    /// Unencrypted Bitwarden JSON
+    Bitwarden,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Field {
+    Password,
+    Username,
+    Uri,
+    Notes,
+    Totp,
+    Json,
+}
+
+// Run a non-interactive command, print its output to out and return the process exit code
+pub fn run(cli: Cli, command: Command, out: &mut impl Write) -> i32 {
+    let backend_url = match cli.url.or_else(url_storage::is_url_in_storage) {
+        Some(url) => url.trim_end_matches('/').to_string(),
+        None => {
+            view::error("no backend URL configured, pass --url or run the interactive setup");
+            return EXIT_ERROR;
+        }
+    };
+    let email = match cli.email {
+        Some(email) => email,
+        None => login::get_mail_from_storage(),
+    };
+    if email.is_empty() {
+        view::error("no e-mail configured, pass --email or set PASSHUB_EMAIL");
+        return EXIT_ERROR;
+    }
+
+    let client = match RsPassClient::new(&backend_url) {
+        Ok(client) => client,
+        Err(e) => {
+            view::error(&e.to_string());
+            return exit_code(&e);
+        }
+    };
+    if !url_check::api::check_health(&client) {
+        view::error(&format!("backend on {} not reachable", backend_url));
+        return EXIT_BACKEND;
+    }
+
+    let (session, mut master_key) = match authenticate(&client, &email) {
+        Ok(session) => session,
+        Err(code) => return code,
+    };
+
+    let code = match command {
+        Command::Login => {
+            url_storage::create_file();
+            url_storage::save_backend_url(&backend_url);
+            login::save_email_to_storage(&email);
+            view::message(out, &format!("Logged in to {} as {}", backend_url, email));
+            EXIT_OK
+        }
+        command => with_entries(&session, &master_key, command, out),
+    };
+
+    master_key.zeroize();
+    _ = api::logout(&session);
+    code
+}
+
+fn authenticate(client: &RsPassClient, email: &str) -> Result<(RsPassClient, String), i32> {
+    let mut password = match std::env::var("PASSHUB_PASSWORD") {
+        Ok(password) => password,
+        Err(_) => rpassword::prompt_password("Master password: ").map_err(|e| {
+            view::error(&format!("could not read master password: {}", e));
+            EXIT_ERROR
+        })?,
+    };
+
+    let result = login::login_with_credentials(client, email, &password);
+    password.zeroize();
+
+    result.map_err(|e| match e {
+        LoginError::Argon2 => {
+            view::error("Argon2 password hashing failed");
+            EXIT_ERROR
+        }
+        LoginError::Backend(e) => {
+            match e.status() {
+                Some(400) => view::error("invalid payload"),
+                Some(401) => view::error("login failed, please check your credentials"),
+                Some(404) => view::error("user not found"),
+                _ => view::error(&format!("login failed: {}", e)),
+            }
+            match e.status() {
+                Some(400 | 401 | 404) => EXIT_AUTH,
+                _ => exit_code(&e),
+            }
+        }
+    })
+}
+
+fn with_entries(
+    client: &RsPassClient,
+    master_key: &str,
+    command: Command,
+    out: &mut impl Write,
+) -> i32 {
+    let (mut entries, mut revision) = match fetch_entries(client, master_key) {
+        Ok(vault) => vault,
+        Err(code) => return code,
+    };
+
+    match command {
+        Command::List {
+            folder,
+            tag,
+            favorites,
+        } => {
+            let folder = match folder {
+                Some(folder) => FolderFilter::Folder(folder.trim_matches('/').to_string()),
+                None => FolderFilter::All,
+            };
+            let hits = menue::filter_entries(&entries, "", &folder, tag.as_deref());
+            let listed: Vec<&Entry> = hits
+                .iter()
+                .map(|hit| &entries[hit.index])
+                .filter(|entry| entry.favorite || !favorites)
+                .collect();
+            view::print_entries(out, &listed);
+            EXIT_OK
+        }
+        Command::Get { name, field } => match find_entry(&entries, &name) {
+            Some(index) => print_field(out, &entries[index], field),
+            None => {
+                view::error(&format!("no entry named '{}'", name));
+                EXIT_NOT_FOUND
+            }
+        },
+        Command::Add {
+            name,
+            username,
+            uri,
+            notes,
+            totp,
+            folder,
+            tag,
+        } => {
+            if find_entry(&entries, &name).is_some() {
+                view::error(&format!("an entry named '{}' already exists", name));
+                return EXIT_ERROR;
+            }
+            // Never an option, arguments are visible to other processes and end up in shell history
+            let password = match std::env::var("PASSHUB_ENTRY_PASSWORD") {
+                Ok(password) => password,
+                Err(_) => match rpassword::prompt_password("Entry password: ") {
+                    Ok(password) => password,
+                    Err(e) => {
+                        view::error(&format!("could not read entry password: {}", e));
+                        return EXIT_ERROR;
+                    }
+                },
+            };
+            if [&name, &password]
+                .iter()
+                .any(|s| !menue::validate_string_length(s))
+            {
+                view::error("fields must not be longer than 200 characters");
+                return EXIT_ERROR;
+            }
+
+            let mut entry = Entry {
+                name,
+                notes,
+                login: Login {
+                    uris: uri.into_iter().map(Uri::new).collect(),
+                    username,
+                    password,
+                    totp,
+                    password_history: Vec::new(),
+                },
+                folder,
+                tags: tag,
+                ..Entry::new(ItemType::Login)
+            };
+            menue::normalize_folder_and_tags(&mut entry);
+            let base = entries.clone();
+            entries.push(entry);
+            upload_entries(client, master_key, base, entries, &mut revision)
+        }
+        Command::Rm { name } => match find_entry(&entries, &name) {
+            Some(index) => {
+                let base = entries.clone();
+                entries.remove(index);
+                upload_entries(client, master_key, base, entries, &mut revision)
+            }
+            None => {
+                view::error(&format!("no entry named '{}'", name));
+                EXIT_NOT_FOUND
+            }
+        },
+        Command::Export { output, format } => {
+            let format = match format {
+                ExportKind::Encrypted => ExportFormat::Encrypted,
+                ExportKind::Bitwarden => ExportFormat::Bitwarden,
+            };
+            export_entries(out, &entries, &output, format)
+        }
+        Command::Login => EXIT_OK,
+    }
+}
+
+fn export_entries(
+    out: &mut impl Write,
+    entries: &[Entry],
+    output: &Path,
+    format: ExportFormat,
+) -> i32 {
+    if output.exists() {
+        view::error(&format!("{} already exists", output.display()));
+        return EXIT_ERROR;
+    }
+
+    let mut content = match export::to_bitwarden_json(entries) {
+        Ok(content) => content,
+        Err(e) => {
+            view::error(&format!("serialization error: {}", e));
+            return EXIT_ERROR;
+        }
+    };
+
+    if format == ExportFormat::Encrypted {
+        let mut password = match export_password() {
+            Ok(password) => password,
+            Err(code) => {
+                content.zeroize();
+                return code;
+            }
+        };
+        let encrypted = export::encrypt_export(&content, &password);
+        password.zeroize();
+        content.zeroize();
+        content = match encrypted {
+            Ok(encrypted) => encrypted,
+            Err(e) => {
+                view::error(&format!("the export could not be encrypted: {}", e));
+                return EXIT_ERROR;
+            }
+        };
+    }
+
+    let result = export::write_file(output, &content, false);
+    content.zeroize();
+    match result {
+        Ok(()) => {
+            view::message(
+                out,
+                &format!("Exported {} entries to {}", entries.len(), output.display()),
+            );
+            EXIT_OK
+        }
+        Err(e) => {
+            view::error(&format!("{} could not be written: {}", output.display(), e));
+            EXIT_ERROR
+        }
+    }
+}
+
+fn export_password() -> Result<String, i32> {
+    if let Ok(password) = std::env::var("PASSHUB_EXPORT_PASSWORD") {
+        return Ok(password);
+    }
+
+    let read = |prompt: &str| {
+        rpassword::prompt_password(prompt).map_err(|e| {
+            view::error(&format!("could not read export password: {}", e));
+            EXIT_ERROR
+        })
+    };
+    let mut password = read("Export password: ")?;
+    let mut repeated = read("Repeat export password: ")?;
+    let matches = password == repeated;
+    repeated.zeroize();
+
+    if !matches {
+        password.zeroize();
+        view::error("the export passwords do not match");
+        Err(EXIT_ERROR)
+    } else if !validate_password(&password) {
+        password.zeroize();
+        view::error("the export password must be at least 10 characters long and include uppercase letters, lowercase letters, numbers, and special characters");
+        Err(EXIT_ERROR)
+    } else {
+        Ok(password)
+    }
+}
+
+// The entries and the revision they were fetched at
+fn fetch_entries(client: &RsPassClient, master_key: &str) -> Result<(Vec<Entry>, Revision), i32> {
+    match api::fetch(client, master_key) {
+        Ok((Some(json_data), revision)) => menue::deserialize_json(&json_data)
+            .map(|entries| (entries, revision))
+            .map_err(|e| {
+                view::error(&format!("vault could not be parsed: {}", e));
+                EXIT_ERROR
+            }),
+        Ok((None, revision)) => Ok((Vec::new(), revision)),
+        Err(e) => {
+            view::error(&format!("fetching the vault failed: {}", e));
+            Err(exit_code(&e))
+        }
+    }
+}
+
+// Changes saved elsewhere meanwhile are merged in, conflicting edits are not overwritten
+fn upload_entries(
+    client: &RsPassClient,
+    master_key: &str,
+    base: Vec<Entry>,
+    mut entries: Vec<Entry>,
+    revision: &mut Revision,
+) -> i32 {
+    match sync::save(
+        client,
+        master_key,
+        base,
+        &mut entries,
+        revision,
+        &mut |_| None,
+    ) {
+        Ok(()) => EXIT_OK,
+        Err(PassHubError::Conflict) => {
+            view::error(
+                "the entry was changed on another device at the same time, nothing was saved",
+            );
+            EXIT_BACKEND
+        }
+        Err(e) => {
+            view::error(&format!("uploading the vault failed: {}", e));
+            exit_code(&e)
+        }
+    }
+}
+
+// Rejected sessions and keys are authentication failures, damaged data a general error
+fn exit_code(error: &PassHubError) -> i32 {
+    match error {
+        PassHubError::Http { status: 401, .. } | PassHubError::Decryption => EXIT_AUTH,
+        PassHubError::CorruptVault(_)
+        | PassHubError::Serialization(_)
+        | PassHubError::Config(_) => EXIT_ERROR,
+        _ => EXIT_BACKEND,
+    }
+}
+
+// Look up an entry by id first, then by exact name
+fn find_entry(entries: &[Entry], name: &str) -> Option<usize> {
+    entries
+        .iter()
+        .position(|entry| entry.id == name)
+        .or_else(|| entries.iter().position(|entry| entry.name == name))
+}
+
+fn print_field(out: &mut impl Write, entry: &Entry, field: Field) -> i32 {
+    let value = match field {
+        // Only logins have credentials, other types are read with --field json
+        Field::Password | Field::Username | Field::Uri | Field::Totp
+            if entry.item_type != ItemType::Login =>
+        {
+            None
+        }
+        Field::Password => Some(entry.login.password.clone()),
+        Field::Username => entry.login.username.clone(),
+        // One URI per line
+        Field::Uri if !entry.login.uris.is_empty() => Some(
+            entry
+                .login
+                .uris
+                .iter()
+                .map(|uri| uri.uri.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        Field::Uri => None,
+        Field::Notes => entry.notes.clone(),
+        Field::Totp => match entry.login.totp.as_deref() {
+            Some(totp) => match Totp::parse(totp) {
+                Some(totp) => Some(totp.generate_now()),
+                None => {
+                    view::error("the stored TOTP secret is invalid");
+                    return EXIT_ERROR;
+                }
+            },
+            None => None,
+        },
+        Field::Json => serde_json::to_string_pretty(entry).ok(),
+    };
+
+    match value {
+        Some(value) => {
+            view::message(out, &value);
+            EXIT_OK
+        }
+        None => {
+            view::error("the entry has no value for this field");
+            EXIT_NOT_FOUND
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::login::kdf::KdfParams;
+    use crate::testing::{self, isolated, PASSWORD};
+    use std::sync::MutexGuard;
+
+    const EMAIL: &str = "cli@example.com";
+
+    // A mock backend with one account whose vault holds a single login.
+    // The guard also keeps other tests away from the password variables.
+    fn backend() -> (MutexGuard<'static, ()>, String) {
+        let home = isolated();
+        let (client, url) = testing::mock(None, true);
+        let (session, master_key) = testing::register(&client, EMAIL, &KdfParams::random());
+        let mail = Entry {
+            name: "mail".to_string(),
+            login: Login {
+                username: Some("alice".to_string()),
+                password: "secret".to_string(),
+                uris: vec![Uri::new("https://mail.example.com".to_string())],
+                ..Login::default()
+            },
+            ..Entry::new(ItemType::Login)
+        };
+        let vault = serde_json::to_value([mail]).unwrap();
+        api::update(&session, &master_key, &vault, None).unwrap();
+        std::env::set_var("PASSHUB_PASSWORD", PASSWORD);
+        for var in ["PASSHUB_ENTRY_PASSWORD", "PASSHUB_EXPORT_PASSWORD"] {
+            std::env::remove_var(var);
+        }
+        (home, url)
+    }
+
+    // Run passhub with args against url, returns the exit code and the output
+    fn passhub(url: &str, args: &[&str]) -> (i32, String) {
+        let mut cli = Cli::try_parse_from(
+            ["passhub", "--url", url, "--email", EMAIL]
+                .iter()
+                .chain(args),
+        )
+        .unwrap();
+        let command = cli.command.take().unwrap();
+        let mut out = Vec::new();
+        let code = run(cli, command, &mut out);
+        (code, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn login_list_and_get() {
+        let (_home, url) = backend();
+        let (code, out) = passhub(&url, &["login"]);
+        assert_eq!(code, EXIT_OK);
+        assert_eq!(out, format!("Logged in to {} as {}\n", url, EMAIL));
+
+        let (code, out) = passhub(&url, &["list"]);
+        assert_eq!(code, EXIT_OK);
+        assert_eq!(out, "mail\talice\thttps://mail.example.com\n");
+        assert_eq!(
+            passhub(&url, &["list", "--favorites"]),
+            (EXIT_OK, String::new())
+        );
+
+        assert_eq!(
+            passhub(&url, &["get", "mail"]),
+            (EXIT_OK, "secret\n".into())
+        );
+        let username = passhub(&url, &["get", "mail", "--field", "username"]);
+        assert_eq!(username, (EXIT_OK, "alice\n".into()));
+    }
+
+    #[test]
+    fn wrong_master_password_is_an_authentication_failure() {
+        let (_home, url) = backend();
+        std::env::set_var("PASSHUB_PASSWORD", "Wrong-Horse-9");
+        assert_eq!(passhub(&url, &["list"]), (EXIT_AUTH, String::new()));
+        assert_eq!(passhub(&url, &["login"]), (EXIT_AUTH, String::new()));
+    }
+
+    #[test]
+    fn missing_entries_and_fields_are_not_found() {
+        let (_home, url) = backend();
+        assert_eq!(passhub(&url, &["get", "bank"]).0, EXIT_NOT_FOUND);
+        assert_eq!(passhub(&url, &["rm", "bank"]).0, EXIT_NOT_FOUND);
+        assert_eq!(
+            passhub(&url, &["get", "mail", "--field", "notes"]).0,
+            EXIT_NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn add_and_rm() {
+        let (_home, url) = backend();
+        std::env::set_var("PASSHUB_ENTRY_PASSWORD", "hunter2");
+        let args = ["add", "bank", "--username", "bob", "--folder", "Finance"];
+        assert_eq!(passhub(&url, &args), (EXIT_OK, String::new()));
+        assert_eq!(passhub(&url, &args).0, EXIT_ERROR); // Already exists
+        assert_eq!(
+            passhub(&url, &["get", "bank"]),
+            (EXIT_OK, "hunter2\n".into())
+        );
+        let (_, out) = passhub(&url, &["list", "--folder", "Finance"]);
+        assert_eq!(out, "bank\tbob\t\n");
+
+        assert_eq!(passhub(&url, &["rm", "bank"]), (EXIT_OK, String::new()));
+        assert_eq!(passhub(&url, &["get", "bank"]).0, EXIT_NOT_FOUND);
+        assert_eq!(
+            passhub(&url, &["list"]).1,
+            "mail\talice\thttps://mail.example.com\n"
+        );
+    }
+
+    #[test]
+    fn export_encrypted_and_bitwarden() {
+        let (_home, url) = backend();
+        let dir = tempfile::tempdir().unwrap();
+        let encrypted = dir.path().join("vault.json");
+        let path = encrypted.to_str().unwrap();
+        std::env::set_var("PASSHUB_EXPORT_PASSWORD", "Export-Horse-9");
+        let (code, out) = passhub(&url, &["export", path]);
+        assert_eq!(code, EXIT_OK);
+        assert_eq!(out, format!("Exported 1 entries to {}\n", path));
+        let content = std::fs::read_to_string(&encrypted).unwrap();
+        let decrypted = export::decrypt_export(&content, "Export-Horse-9").unwrap();
+        assert!(decrypted.contains("secret"));
+        assert_eq!(passhub(&url, &["export", path]).0, EXIT_ERROR); // Never overwritten
+
+        let plain = dir.path().join("bitwarden.json");
+        let path = plain.to_str().unwrap();
+        assert_eq!(
+            passhub(&url, &["export", path, "--format", "bitwarden"]).0,
+            EXIT_OK
+        );
+        let content = std::fs::read_to_string(&plain).unwrap();
+        assert!(!export::is_encrypted_export(&content));
+        assert!(content.contains("\"password\": \"secret\""));
+    }
+
+    #[test]
+    fn unreachable_backend() {
+        let (_home, _) = backend();
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{}", port);
+        assert_eq!(passhub(&url, &["list"]), (EXIT_BACKEND, String::new()));
+    }
+}
@@ -0,0 +1,29 @@
+use crate::menue::logik::Entry;
+use std::io::Write;
+
+// Output goes to out (stdout outside of tests), a closed pipe is not an error
+pub fn message(out: &mut impl Write, message: &str) {
+    _ = writeln!(out, "{}", message);
+}
+
+pub fn error(message: &str) {
+    eprintln!("passhub: {}", message);
+}
+
+// One entry per line: name, username and first URI separated by tabs
+pub fn print_entries(out: &mut impl Write, entries: &[&Entry]) {
+    for entry in entries {
+        _ = writeln!(
+            out,
+            "{}\t{}\t{}",
+            entry.name,
+            entry.login.username.as_deref().unwrap_or(""),
+            entry
+                .login
+                .uris
+                .first()
+                .map(|uri| uri.uri.as_str())
+                .unwrap_or("")
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use crate::error::{PassHubError, Result};
+use crate::settings::logik::{self, Settings};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use log::debug;
+use reqwest::blocking::{Client, Request, RequestBuilder, Response};
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{Certificate, Method, Proxy, StatusCode};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::sleep;
+use std::time::Duration;
+
+// Gateway answers after which the request is sent again
+const RETRY_STATUSES: [StatusCode; 3] = [
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+// Asked for a new JWT token when the backend rejects the current one
+type Reauthenticate = Arc<dyn Fn() -> Option<String> + Send + Sync>;
+
+// Connection to an rsPass backend, shared by all modules.
+// Cloning is cheap and keeps the connection pool, with_token adds the session.
+#[derive(Clone)]
+pub struct RsPassClient {
+    base_url: String,
+    token: Arc<Mutex<Option<String>>>, // Shared by all clones, so a renewed token reaches them all
+    reauthenticate: Option<Reauthenticate>,
+    http: Client,
+    attempts: u32, // Tries per request, 1 disables retries
+}
+
+impl RsPassClient {
+    // Build a client with the connection options from settings.json
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_settings(base_url, &logik::load())
+    }
+
+    pub fn with_settings(base_url: &str, settings: &Settings) -> Result<Self> {
+        let mut builder = Client::builder()
+            .user_agent(concat!("PassHub/", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(seconds(settings.connect_timeout_seconds))
+            .timeout(seconds(settings.request_timeout_seconds));
+
+        if settings.ca_bundle.is_some() && settings.pinned_certificate.is_some() {
+            return Err(PassHubError::Config(
+                "ca_bundle and pinned_certificate cannot be combined".to_string(),
+            ));
+        }
+        // Extra CAs for backends with a certificate from a private CA
+        if let Some(path) = &settings.ca_bundle {
+            for certificate in
+                Certificate::from_pem_bundle(&read(path)?).map_err(|e| config_error(path, e))?
+            {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        // A pinned certificate is the only one trusted, e.g. a self-signed one
+        if let Some(path) = &settings.pinned_certificate {
+            let certificate =
+                Certificate::from_pem(&read(path)?).map_err(|e| config_error(path, e))?;
+            builder = builder
+                .tls_built_in_root_certs(false)
+                .add_root_certificate(certificate);
+        }
+        // Without a proxy in the settings the system proxy variables apply
+        if let Some(url) = &settings.proxy_url {
+            let proxy = Proxy::all(url)
+                .map_err(|e| PassHubError::Config(format!("proxy_url {}: {}", url, e)))?;
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(RsPassClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: Arc::new(Mutex::new(None)),
+            reauthenticate: None,
+            http: builder.build()?,
+            attempts: settings.retry_attempts.max(1),
+        })
+    }
+
+    // Same connection, requests are sent with the JWT token
+    pub fn with_token(&self, token: &str) -> Self {
+        RsPassClient {
+            token: Arc::new(Mutex::new(Some(token.to_string()))),
+            reauthenticate: None,
+            ..self.clone()
+        }
+    }
+
+    // Same connection without a session, e.g. to log in again
+    pub fn without_token(&self) -> Self {
+        RsPassClient {
+            token: Arc::new(Mutex::new(None)),
+            reauthenticate: None,
+            ..self.clone()
+        }
+    }
+
+    // Same session, a rejected token is replaced by the one reauthenticate returns
+    // and the request is sent again. None gives up and returns the 401.
+    pub fn on_expiry(
+        &self,
+        reauthenticate: impl Fn() -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        RsPassClient {
+            reauthenticate: Some(Arc::new(reauthenticate)),
+            ..self.clone()
+        }
+    }
+
+    // When the current JWT token expires, None without a session or exp claim
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        token_expiry(self.token.lock().unwrap().as_deref()?)
+    }
+
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.http.get(self.url(path))
+    }
+
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.http.post(self.url(path))
+    }
+
+    // Send the request with the session token, retried if the backend could not be reached.
+    // An answer with an error status is returned as PassHubError::Http.
+    pub fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request.build()?;
+        // A 401 from the login endpoints means wrong credentials, not an expired session
+        let again = if request.url().path().contains("/api/v1/auth/") {
+            None
+        } else {
+            request.try_clone()
+        };
+        let response = self.execute(request)?;
+
+        // An expired session is renewed once and the request sent again
+        match again {
+            Some(again) if response.status() == StatusCode::UNAUTHORIZED && self.renew() => {
+                check(self.execute(again)?)
+            }
+            _ => check(response),
+        }
+    }
+
+    fn execute(&self, mut request: Request) -> reqwest::Result<Response> {
+        if let Some(token) = self.token.lock().unwrap().as_deref() {
+            if let Ok(mut value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+                value.set_sensitive(true);
+                request.headers_mut().insert(AUTHORIZATION, value);
+            }
+        }
+
+        let mut attempt = 1;
+        loop {
+            // Requests with a body that cannot be copied are sent only once
+            let retry = match request.try_clone() {
+                Some(retry) if attempt < self.attempts => retry,
+                _ => return self.http.execute(request),
+            };
+            match self.http.execute(retry) {
+                Ok(response) if !retryable(request.method(), response.status()) => {
+                    return Ok(response)
+                }
+                Ok(response) => debug!("{} try failed: {}", attempt, response.status()),
+                // Only connect errors are retried, anything else may have reached the backend
+                Err(e) if e.is_connect() => debug!("{} try failed: {}", attempt, e),
+                Err(e) => return Err(e),
+            }
+            attempt += 1;
+            sleep(RETRY_DELAY);
+        }
+    }
+
+    // Ask for a new token, false if there is no session or the user gave up
+    fn renew(&self) -> bool {
+        let reauthenticate = match &self.reauthenticate {
+            Some(reauthenticate) if self.token.lock().unwrap().is_some() => reauthenticate,
+            _ => return false,
+        };
+        match reauthenticate() {
+            Some(token) => {
+                *self.token.lock().unwrap() = Some(token);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+// Read the exp claim of a JWT token. The signature is not checked, only the backend can.
+pub fn token_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let payload = URL_SAFE_NO_PAD
+        .decode(token.split('.').nth(1)?.trim_end_matches('='))
+        .ok()?;
+    let claims: Value = serde_json::from_slice(&payload).ok()?;
+    DateTime::from_timestamp(claims["exp"].as_i64()?, 0)
+}
+
+// A gateway error may come after the backend got the request, e.g. a vault update
+// may have been stored. Only idempotent requests are sent again then, POSTs only
+// after connect errors.
+fn retryable(method: &Method, status: StatusCode) -> bool {
+    RETRY_STATUSES.contains(&status) && method.is_idempotent()
+}
+
+fn check(response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(PassHubError::from_response(response))
+    }
+}
+
+// A timeout of 0 waits forever
+fn seconds(seconds: u64) -> Option<Duration> {
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| config_error(path, e))
+}
+
+fn config_error(path: &Path, e: impl std::fmt::Display) -> PassHubError {
+    PassHubError::Config(format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use tiny_http::{Response as MockResponse, Server};
+
+    // Client for a server that answers every request with status, counts the requests
+    fn gateway(status: u16) -> (RsPassClient, Arc<AtomicUsize>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = request.respond(MockResponse::empty(status));
+            }
+        });
+        let settings = Settings {
+            retry_attempts: 2,
+            ..Settings::default()
+        };
+        let url = format!("http://127.0.0.1:{}", port);
+        (
+            RsPassClient::with_settings(&url, &settings).unwrap(),
+            requests,
+        )
+    }
+
+    #[test]
+    fn posts_are_not_sent_again_after_a_gateway_error() {
+        for status in [502, 503, 504] {
+            let (client, requests) = gateway(status);
+            let result = client.send(client.post("/api/v1/sync/update").body("{}"));
+            assert_eq!(result.unwrap_err().status(), Some(status));
+            assert_eq!(requests.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[test]
+    fn reads_are_sent_again_after_a_gateway_error() {
+        let (client, requests) = gateway(502);
+        let result = client.send(client.get("/range/5BAA6"));
+        assert_eq!(result.unwrap_err().status(), Some(502));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn tls_failures_are_told_apart_from_network_errors() {
+        // A server that answers the TLS handshake in plain HTTP
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("https://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let _ = stream.read(&mut [0; 1024]);
+                let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n");
+            }
+        });
+        let client = RsPassClient::with_settings(&url, &Settings::default()).unwrap();
+        let result = client.send(client.get("/"));
+        assert!(
+            matches!(result, Err(PassHubError::Tls(_))),
+            "{:?}",
+            result.err()
+        );
+
+        // Nothing listens on the port of a dropped listener
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{}", port);
+        let client = RsPassClient::with_settings(&url, &Settings::default()).unwrap();
+        let result = client.send(client.get("/"));
+        assert!(
+            matches!(result, Err(PassHubError::Network(_))),
+            "{:?}",
+            result.err()
+        );
+    }
+}
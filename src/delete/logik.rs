@@ -1,11 +1,13 @@
 use super::{api, view};
+use crate::client::RsPassClient;
+use crate::menue::cache;
 
-pub fn delete(backend_url: &String, jwt_token: &String) {
-    let status_code = api::delete(backend_url, jwt_token);
-
-    match status_code.expect("Delete: Error occurred deleting the account") {
-        200 => view::draw_delete_screen(),
-        400 | 401 | 500 => view::error(),
-        _ => view::error(),
+pub fn delete(client: &RsPassClient) {
+    match api::delete(client) {
+        Ok(()) => {
+            cache::clear(); // The cached vault belongs to the deleted account
+            view::draw_delete_screen()
+        }
+        Err(e) => view::error(&e.to_string()),
     }
 }
@@ -5,7 +5,7 @@ use crossterm::{
 };
 use ratatui::{
     backend::CrosstermBackend,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Wrap},
     Terminal,
 };
 use std::io::{self};
@@ -43,7 +43,7 @@ pub fn draw_delete_screen() {
     execute!(io::stdout(), LeaveAlternateScreen).unwrap();
 }
 
-pub fn error() {
+pub fn error(message: &str) {
     // Setup terminal for error screen
     enable_raw_mode().unwrap();
     let mut stdout = io::stdout();
@@ -57,8 +57,12 @@ pub fn error() {
                 let size = frame.area();
                 let block = Block::default().borders(Borders::ALL).title("Error");
 
-                let paragraph =
-                    Paragraph::new("An error occoured while deleting your account.").block(block);
+                let paragraph = Paragraph::new(format!(
+                    "An error occoured while deleting your account. {}",
+                    message
+                ))
+                .wrap(Wrap { trim: true })
+                .block(block);
 
                 frame.render_widget(paragraph, size);
             })
@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::fmt;
+
+// Everything that can go wrong talking to the backend or opening the vault.
+// The views turn these into messages instead of guessing from status codes.
+#[derive(Debug)]
+pub enum PassHubError {
+    Network(String), // The backend could not be reached or the connection broke
+    Tls(String),     // The HTTPS connection could not be established securely
+    Http { status: u16, body: String }, // The backend answered with an error status
+    Decryption,      // Wrong key or tampered data, AES-GCM authentication failed
+    CorruptVault(String), // The vault decrypted, but its content is not valid
+    Serialization(String),
+    Conflict,       // The vault was changed on another device and the merge was not finished
+    Config(String), // The connection settings could not be applied
+}
+
+pub type Result<T> = std::result::Result<T, PassHubError>;
+
+// Error bodies can hold whole HTML pages, only the start is shown
+const MAX_BODY_LENGTH: usize = 200;
+
+impl PassHubError {
+    // Read the status and body of a failed response
+    pub fn from_response(response: reqwest::blocking::Response) -> Self {
+        let status = response.status().as_u16();
+        let body = response.text().unwrap_or_default();
+        PassHubError::Http {
+            status,
+            body: body.trim().chars().take(MAX_BODY_LENGTH).collect(),
+        }
+    }
+
+    // HTTP status of the failed request, if the backend answered at all
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            PassHubError::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    // The backend could not be reached, the cached vault may still be opened
+    pub fn is_offline(&self) -> bool {
+        matches!(self, PassHubError::Network(_) | PassHubError::Tls(_))
+    }
+}
+
+impl fmt::Display for PassHubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PassHubError::Network(e) => write!(f, "The backend could not be reached: {}", e),
+            PassHubError::Tls(e) => write!(f, "The secure connection to the backend failed: {}", e),
+            PassHubError::Http { status, body } if body.is_empty() => {
+                write!(f, "The backend answered with status {}", status)
+            }
+            PassHubError::Http { status, body } => {
+                write!(f, "The backend answered with status {}: {}", status, body)
+            }
+            PassHubError::Decryption => write!(
+                f,
+                "The vault could not be decrypted. The key is wrong or the data was tampered with."
+            ),
+            PassHubError::CorruptVault(e) => write!(f, "The vault is damaged: {}", e),
+            PassHubError::Serialization(e) => write!(f, "The data could not be converted: {}", e),
+            PassHubError::Conflict => write!(
+                f,
+                "The vault was changed on another device at the same time and could not be merged."
+            ),
+            PassHubError::Config(e) => write!(f, "The connection settings are invalid: {}", e),
+        }
+    }
+}
+
+impl Error for PassHubError {}
+
+impl From<reqwest::Error> for PassHubError {
+    fn from(e: reqwest::Error) -> Self {
+        // reqwest has no TLS error kind, the TLS backend's own error is somewhere in the chain
+        let mut root: &dyn Error = &e;
+        let mut source: Option<&dyn Error> = Some(&e);
+        while let Some(cause) = source {
+            if let Some(tls) = cause.downcast_ref::<native_tls::Error>() {
+                return PassHubError::Tls(tls.to_string());
+            }
+            root = cause;
+            source = cause.source();
+        }
+        if e.is_decode() {
+            PassHubError::Serialization(e.to_string())
+        } else {
+            // The innermost cause says what actually failed, e.g. "Connection refused"
+            PassHubError::Network(root.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for PassHubError {
+    fn from(e: serde_json::Error) -> Self {
+        PassHubError::Serialization(e.to_string())
+    }
+}
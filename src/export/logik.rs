@@ -0,0 +1,471 @@
+use super::view;
+use crate::error::{PassHubError, Result};
+use crate::lock::logik::IdleTimer;
+use crate::menue::logik::{self as menue, Entry, ItemType};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zeroize::Zeroize;
+
+// Identifies an encrypted PassHub export, the cleartext is a Bitwarden JSON export
+pub const FORMAT_NAME: &str = "passhub-encrypted-export";
+// Version 2 authenticates everything but the ciphertext as associated data
+const FORMAT_VERSION: u32 = 2;
+const FORMAT_VERSION_UNAUTHENTICATED_HEADER: u32 = 1;
+
+// Same cost as the master key derivation
+const KDF_MEMORY_KIB: u32 = 65536;
+const KDF_ITERATIONS: u32 = 3;
+const KDF_PARALLELISM: u32 = 4;
+// Upper bounds for parameters read from a file, so a crafted export cannot exhaust memory
+// or keep the import busy for hours
+const KDF_MAX_MEMORY_KIB: u32 = 1048576;
+const KDF_MAX_ITERATIONS: u32 = 64;
+const KDF_MAX_PARALLELISM: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Bitwarden,
+    Encrypted,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedExport {
+    format: String,
+    version: u32,
+    kdf: KdfParams,
+    cipher: CipherParams,
+    data: String, // Base64 ciphertext including the GCM tag
+}
+
+// The fields of EncryptedExport before the ciphertext
+#[derive(Serialize)]
+struct ExportHeader<'a> {
+    format: &'a str,
+    version: u32,
+    kdf: &'a KdfParams,
+    cipher: &'a CipherParams,
+}
+
+impl EncryptedExport {
+    fn header(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(&ExportHeader {
+            format: &self.format,
+            version: self.version,
+            kdf: &self.kdf,
+            cipher: &self.cipher,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CipherParams {
+    algorithm: String,
+    nonce: String,
+}
+
+// Ask for format, file and password and write the export.
+// Gives up once idle_timer expires, the caller has to lock the vault then.
+pub fn export(entries: &[Entry], idle_timer: &mut IdleTimer) {
+    let (format, path) = match view::ask_for_export(idle_timer) {
+        Some(choice) => choice,
+        None => return,
+    };
+    let overwrite = Path::new(&path).exists();
+    if overwrite && !view::confirm_overwrite(&path) {
+        return;
+    }
+
+    let mut content = match to_bitwarden_json(entries) {
+        Ok(content) => content,
+        Err(e) => {
+            view::error(&e.to_string());
+            return;
+        }
+    };
+
+    if format == ExportFormat::Encrypted {
+        let mut password = match view::ask_for_password(true, idle_timer) {
+            Some(password) => password,
+            None => {
+                content.zeroize();
+                return;
+            }
+        };
+        let encrypted = encrypt_export(&content, &password);
+        password.zeroize();
+        content.zeroize();
+        content = match encrypted {
+            Ok(encrypted) => encrypted,
+            Err(e) => {
+                view::error(&format!("The export could not be encrypted: {}", e));
+                return;
+            }
+        };
+    }
+
+    let result = write_file(Path::new(&path), &content, overwrite);
+    content.zeroize();
+    match result {
+        Ok(()) => view::success(&path, format),
+        Err(e) => view::error(&format!("The file could not be written: {}", e)),
+    }
+}
+
+// Unencrypted export in the format of Bitwarden, readable by most password managers
+pub fn to_bitwarden_json(entries: &[Entry]) -> Result<String> {
+    // Bitwarden folders have an id, nested folders are named with "/" like in PassHub
+    let folders: Vec<(String, String)> = menue::folders(entries)
+        .into_iter()
+        .map(|folder| (menue::generate_id(), folder))
+        .collect();
+    let folder_id = |entry: &Entry| {
+        folders
+            .iter()
+            .find(|(_, name)| Some(name.as_str()) == entry.folder.as_deref())
+            .map(|(id, _)| id.clone())
+    };
+
+    let items: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let uris: Vec<_> = entry
+                .login
+                .uris
+                .iter()
+                .filter(|uri| !uri.uri.is_empty())
+                .map(|uri| json!({ "match": uri.match_type, "uri": uri.uri }))
+                .collect();
+            let mut item = json!({
+                "id": entry.id,
+                "organizationId": null,
+                "folderId": folder_id(entry),
+                "type": entry.item_type,
+                "reprompt": 0,
+                "name": entry.name,
+                "notes": entry.notes,
+                "favorite": entry.favorite,
+                "fields": entry.fields,
+                "passwordHistory": entry.login.password_history,
+                "creationDate": entry.created,
+                "revisionDate": entry.modified,
+                "collectionIds": null,
+                // Not part of the Bitwarden format, other managers ignore them
+                "passhubTags": entry.tags,
+                "passhubLastUsedDate": entry.last_used,
+            });
+            // Bitwarden only expects the object of the item's own type
+            item[type_key(entry.item_type)] = match entry.item_type {
+                ItemType::Login => json!({
+                    "uris": uris,
+                    "username": entry.login.username,
+                    "password": entry.login.password,
+                    "totp": entry.login.totp,
+                }),
+                ItemType::SecureNote => json!(entry.secure_note.clone().unwrap_or_default()),
+                ItemType::Card => json!(entry.card.clone().unwrap_or_default()),
+                ItemType::Identity => json!(entry.identity.clone().unwrap_or_default()),
+                ItemType::SshKey => json!(entry.ssh_key.clone().unwrap_or_default()),
+            };
+            item
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&json!({
+        "encrypted": false,
+        "folders": folders
+            .iter()
+            .map(|(id, name)| json!({ "id": id, "name": name }))
+            .collect::<Vec<_>>(),
+        "items": items,
+    }))?)
+}
+
+fn type_key(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::Login => "login",
+        ItemType::SecureNote => "secureNote",
+        ItemType::Card => "card",
+        ItemType::Identity => "identity",
+        ItemType::SshKey => "sshKey",
+    }
+}
+
+// Encrypt the cleartext with a key derived from the export password (Argon2id, AES-256-GCM)
+pub fn encrypt_export(cleartext: &str, password: &str) -> Result<String> {
+    let mut rng = rand::rng();
+    let mut salt = [0u8; 16];
+    rng.fill(&mut salt);
+    let mut nonce = [0u8; 12];
+    rng.fill(&mut nonce);
+
+    let kdf = KdfParams {
+        algorithm: "argon2id".to_string(),
+        memory_kib: KDF_MEMORY_KIB,
+        iterations: KDF_ITERATIONS,
+        parallelism: KDF_PARALLELISM,
+        salt: STANDARD.encode(salt),
+    };
+    let mut key = derive_key(password, &kdf)?;
+    let mut export = EncryptedExport {
+        format: FORMAT_NAME.to_string(),
+        version: FORMAT_VERSION,
+        kdf,
+        cipher: CipherParams {
+            algorithm: "aes-256-gcm".to_string(),
+            nonce: STANDARD.encode(nonce),
+        },
+        data: String::new(),
+    };
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    key.zeroize();
+    let header = export.header()?;
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: cleartext.as_bytes(),
+                aad: &header,
+            },
+        )
+        .map_err(|e| PassHubError::Serialization(format!("Encryption error: {:?}", e)))?;
+    export.data = STANDARD.encode(ciphertext);
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+// Returns the cleartext Bitwarden JSON, fails with Decryption if the password is wrong
+// or the file was changed
+pub fn decrypt_export(content: &str, password: &str) -> Result<String> {
+    let damaged = |reason: String| Err(PassHubError::CorruptVault(reason));
+    let export: EncryptedExport = serde_json::from_str(content)?;
+    if export.format != FORMAT_NAME {
+        return damaged("Not an encrypted PassHub export".to_string());
+    }
+    if ![FORMAT_VERSION, FORMAT_VERSION_UNAUTHENTICATED_HEADER].contains(&export.version) {
+        return damaged(format!("Unsupported export version {}", export.version));
+    }
+    if export.cipher.algorithm != "aes-256-gcm" {
+        return damaged(format!("Unsupported cipher {}", export.cipher.algorithm));
+    }
+
+    let nonce = STANDARD
+        .decode(&export.cipher.nonce)
+        .map_err(|e| PassHubError::CorruptVault(format!("Invalid nonce: {}", e)))?;
+    if nonce.len() != 12 {
+        return damaged("Invalid nonce".to_string());
+    }
+    let ciphertext = STANDARD
+        .decode(&export.data)
+        .map_err(|e| PassHubError::CorruptVault(e.to_string()))?;
+    let header = match export.version {
+        FORMAT_VERSION_UNAUTHENTICATED_HEADER => Vec::new(),
+        _ => export.header()?,
+    };
+
+    let mut key = derive_key(password, &export.kdf)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    key.zeroize();
+    let cleartext = cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: &header,
+            },
+        )
+        .map_err(|_| PassHubError::Decryption)?;
+
+    String::from_utf8(cleartext).map_err(|e| PassHubError::CorruptVault(e.to_string()))
+}
+
+// True if the content looks like an encrypted PassHub export
+pub fn is_encrypted_export(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("format")
+                .and_then(|f| f.as_str())
+                .map(|f| f == FORMAT_NAME)
+        })
+        .unwrap_or(false)
+}
+
+fn derive_key(password: &str, kdf: &KdfParams) -> Result<[u8; 32]> {
+    let invalid = |reason: String| PassHubError::CorruptVault(reason);
+    if kdf.algorithm != "argon2id" {
+        return Err(invalid(format!(
+            "Unsupported key derivation {}",
+            kdf.algorithm
+        )));
+    }
+    if kdf.memory_kib > KDF_MAX_MEMORY_KIB
+        || kdf.iterations > KDF_MAX_ITERATIONS
+        || kdf.parallelism > KDF_MAX_PARALLELISM
+    {
+        return Err(invalid(
+            "Key derivation parameters are too large".to_string(),
+        ));
+    }
+
+    let salt = STANDARD
+        .decode(&kdf.salt)
+        .map_err(|e| invalid(format!("Invalid salt: {}", e)))?;
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| invalid(format!("Invalid key derivation parameters: {}", e)))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut key = [0u8; 32];
+    argon2
+        .hash_password_into(password.as_bytes(), &salt, &mut key)
+        .map_err(|e| invalid(format!("Argon2 error: {}", e)))?;
+    Ok(key)
+}
+
+// Exports contain secrets, so the file is only readable by the current user.
+// Without overwrite an existing file is never touched.
+pub fn write_file(path: &Path, content: &str, overwrite: bool) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    if overwrite {
+        options.write(true).create(true).truncate(true);
+    } else {
+        options.write(true).create_new(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, an existing one keeps its permissions otherwise
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const PASSWORD: &str = "Export-Pass-1";
+    const CLEARTEXT: &str = r#"{"encrypted":false,"items":[]}"#;
+
+    fn changed(content: &str, change: impl FnOnce(&mut Value)) -> String {
+        let mut export: Value = serde_json::from_str(content).unwrap();
+        change(&mut export);
+        export.to_string()
+    }
+
+    #[test]
+    fn encrypt_then_decrypt() {
+        let content = encrypt_export(CLEARTEXT, PASSWORD).unwrap();
+        assert!(is_encrypted_export(&content));
+        assert!(!content.contains("items"));
+        assert_eq!(decrypt_export(&content, PASSWORD).unwrap(), CLEARTEXT);
+    }
+
+    #[test]
+    fn decrypt_rejects_a_wrong_password() {
+        let content = encrypt_export(CLEARTEXT, PASSWORD).unwrap();
+        assert!(decrypt_export(&content, "Wrong-Pass-1").is_err());
+    }
+
+    #[test]
+    fn decrypt_rejects_changed_data_or_header() {
+        let content = encrypt_export(CLEARTEXT, PASSWORD).unwrap();
+        let data = changed(&content, |export| {
+            let mut data = STANDARD.decode(export["data"].as_str().unwrap()).unwrap();
+            data[0] ^= 1;
+            export["data"] = Value::String(STANDARD.encode(data));
+        });
+        assert!(decrypt_export(&data, PASSWORD).is_err());
+
+        // Claiming the unauthenticated version 1 does not strip the header from the tag
+        let downgraded = changed(&content, |export| {
+            export["version"] = FORMAT_VERSION_UNAUTHENTICATED_HEADER.into()
+        });
+        assert!(decrypt_export(&downgraded, PASSWORD).is_err());
+    }
+
+    #[test]
+    fn derive_key_rejects_oversized_parameters() {
+        let kdf = |memory_kib, iterations, parallelism| KdfParams {
+            algorithm: "argon2id".to_string(),
+            memory_kib,
+            iterations,
+            parallelism,
+            salt: STANDARD.encode([0u8; 16]),
+        };
+        for params in [
+            kdf(KDF_MAX_MEMORY_KIB + 1, 1, 1),
+            kdf(64, KDF_MAX_ITERATIONS + 1, 1),
+            kdf(64, 1, KDF_MAX_PARALLELISM + 1),
+        ] {
+            assert!(derive_key(PASSWORD, &params).is_err());
+        }
+        assert!(derive_key(PASSWORD, &kdf(64, 1, 1)).is_ok());
+    }
+
+    #[test]
+    fn is_encrypted_export_only_matches_the_format_name() {
+        assert!(is_encrypted_export(
+            r#"{ "format": "passhub-encrypted-export" }"#
+        ));
+        assert!(!is_encrypted_export(
+            r#"{ "encrypted": false, "items": [] }"#
+        ));
+        assert!(!is_encrypted_export("name,username,password"));
+    }
+
+    #[test]
+    fn write_file_keeps_existing_files_unless_asked() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("export.json");
+        fs::write(&path, "old").unwrap();
+
+        assert!(write_file(&path, "new", false).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        write_file(&path, "new", true).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_file_is_only_readable_by_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let new = directory.path().join("new.json");
+        write_file(&new, "{}", false).unwrap();
+        assert_eq!(mode(&new), 0o600);
+
+        let existing = directory.path().join("existing.json");
+        fs::write(&existing, "old").unwrap();
+        fs::set_permissions(&existing, fs::Permissions::from_mode(0o644)).unwrap();
+        write_file(&existing, "{}", true).unwrap();
+        assert_eq!(mode(&existing), 0o600);
+    }
+}
@@ -0,0 +1,300 @@
+use super::logik::ExportFormat;
+use crate::lock::logik::IdleTimer;
+use crate::register::logik::validate_password;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Terminal,
+};
+use std::io;
+use zeroize::Zeroize;
+
+// Returns the chosen format and file, None if the user pressed ESC or the vault has to be locked
+pub fn ask_for_export(idle_timer: &mut IdleTimer) -> Option<(ExportFormat, String)> {
+    // Setup terminal
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    let mut format = ExportFormat::Encrypted;
+    let mut input = String::new(); // Stores user input
+
+    let result = loop {
+        terminal
+            .draw(|frame| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(
+                        [
+                            Constraint::Min(0),
+                            Constraint::Length(3),
+                            Constraint::Length(3),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(frame.area());
+
+                let text = Paragraph::new(
+                    "Encrypted: protected by an export password, can be imported into PassHub on any rsPass instance.\n\nBitwarden JSON: unencrypted, can be imported into Bitwarden and most other password managers. Anyone who can read the file can read your passwords.",
+                )
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                        .title("Export")
+                        .title_style(Style::default().add_modifier(Modifier::BOLD))
+                        .title_bottom("Change format (Tab), export (Enter), cancel (ESC)"),
+                );
+
+                let format_box = Paragraph::new(match format {
+                    ExportFormat::Encrypted => "[x] Encrypted   [ ] Bitwarden JSON",
+                    ExportFormat::Bitwarden => "[ ] Encrypted   [x] Bitwarden JSON",
+                })
+                .block(Block::default().borders(Borders::ALL).title("Format"));
+
+                let input_box = Paragraph::new(input.as_str())
+                    .block(Block::default().borders(Borders::ALL).title("File"));
+
+                frame.render_widget(text, chunks[0]);
+                frame.render_widget(format_box, chunks[1]);
+                frame.render_widget(input_box, chunks[2]);
+            })
+            .unwrap();
+
+        // Handle user input
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => break None,
+        };
+        if let Event::Key(key_event) = event {
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+                        format = match format {
+                            ExportFormat::Encrypted => ExportFormat::Bitwarden,
+                            ExportFormat::Bitwarden => ExportFormat::Encrypted,
+                        };
+                    }
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Enter if !input.trim().is_empty() => {
+                        break Some((format, input.trim().to_string()));
+                    }
+                    KeyCode::Esc => break None,
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+    result
+}
+
+// Ask for the export password, with confirm a new one is entered twice and validated.
+// None if the user pressed ESC or the vault has to be locked.
+pub fn ask_for_password(confirm: bool, idle_timer: &mut IdleTimer) -> Option<String> {
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    let labels = ["Export password", "Repeat export password"];
+    let field_count = if confirm { 2 } else { 1 };
+    let mut fields = [String::new(), String::new()];
+    let mut focus = 0;
+    let mut error_message = String::new();
+
+    let result = loop {
+        terminal
+            .draw(|f| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .margin(2)
+                    .constraints(
+                        [
+                            Constraint::Length(3),
+                            Constraint::Length(3),
+                            Constraint::Length(3),
+                            Constraint::Min(0),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(f.area());
+
+                let title = Paragraph::new(if confirm {
+                    "Choose a password for the export"
+                } else {
+                    "The file is an encrypted PassHub export"
+                })
+                .style(
+                    Style::default()
+                        .fg(Color::Rgb(255, 163, 26))
+                        .add_modifier(Modifier::BOLD),
+                )
+                .alignment(Alignment::Center)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Export Password")
+                        .title_bottom("Confirm (Enter), cancel (ESC)"),
+                );
+                f.render_widget(title, chunks[0]);
+
+                for (i, label) in labels.iter().take(field_count).enumerate() {
+                    let password_masked: String = "*".repeat(fields[i].len());
+                    let paragraph = Paragraph::new(format!("{}: {}", label, password_masked))
+                        .style(Style::default().fg(Color::White))
+                        .block(Block::default().borders(Borders::ALL).title(if focus == i {
+                            *label
+                        } else {
+                            " "
+                        }));
+                    f.render_widget(paragraph, chunks[i + 1]);
+                }
+
+                let error_paragraph = Paragraph::new(error_message.clone())
+                    .style(Style::default().fg(Color::Red))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true });
+                f.render_widget(error_paragraph, chunks[3]);
+            })
+            .unwrap();
+
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => {
+                fields.iter_mut().for_each(|field| field.zeroize());
+                break None;
+            }
+        };
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                match key.code {
+                    KeyCode::Enter if focus + 1 < field_count => focus += 1,
+                    KeyCode::Enter if !confirm => {
+                        let [password, _] = fields;
+                        break Some(password);
+                    }
+                    KeyCode::Enter => {
+                        if !validate_password(&fields[0]) {
+                            error_message = String::from(
+                                "Invalid password: Must be at least 10 characters long and include uppercase letters, lowercase letters, numbers, and special characters.",
+                            );
+                            fields.iter_mut().for_each(|field| field.zeroize());
+                            focus = 0;
+                        } else if fields[0] != fields[1] {
+                            error_message = String::from("The passwords do not match.");
+                            fields[1].zeroize();
+                        } else {
+                            let [password, mut repeated] = fields;
+                            repeated.zeroize();
+                            break Some(password);
+                        }
+                    }
+                    KeyCode::Esc => {
+                        fields.iter_mut().for_each(|field| field.zeroize());
+                        break None;
+                    }
+                    KeyCode::Backspace => {
+                        fields[focus].pop();
+                    }
+                    KeyCode::Char(c) => fields[focus].push(c),
+                    KeyCode::Up => focus = focus.saturating_sub(1),
+                    KeyCode::Down => focus = (focus + 1).min(field_count - 1),
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+    result
+}
+
+pub fn success(path: &str, format: ExportFormat) {
+    let message = match format {
+        ExportFormat::Encrypted => format!(
+            "The vault has been exported to {}. \nKeep the export password, the file cannot be opened without it. \nPress Enter to continue.",
+            path
+        ),
+        ExportFormat::Bitwarden => format!(
+            "The vault has been exported to {}. \nThe file is not encrypted, delete it once you no longer need it. \nPress Enter to continue.",
+            path
+        ),
+    };
+    message_screen("Export", &message);
+}
+
+pub fn error(message: &str) {
+    message_screen("Error", &format!("{} \nPress Enter to continue.", message));
+}
+
+// True if the user wants to replace the existing file
+pub fn confirm_overwrite(path: &str) -> bool {
+    let message = format!(
+        "{} already exists. \nOverwrite it (Enter) or cancel (ESC)?",
+        path
+    );
+    show_screen("Export", &message, true)
+}
+
+fn message_screen(title: &str, message: &str) {
+    show_screen(title, message, false);
+}
+
+// Shown until Enter is pressed, or ESC if cancellable. True for Enter.
+fn show_screen(title: &str, message: &str, cancellable: bool) -> bool {
+    // Setup terminal for message screen
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    let confirmed = loop {
+        terminal
+            .draw(|frame| {
+                let size = frame.area();
+                let block = Block::default().borders(Borders::ALL).title(title);
+
+                let paragraph = Paragraph::new(message)
+                    .wrap(Wrap { trim: true })
+                    .block(block);
+
+                frame.render_widget(paragraph, size);
+            })
+            .unwrap();
+
+        // Wait for user input to dismiss the screen
+        if let Event::Key(key_event) = event::read().unwrap() {
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Enter => break true,
+                    KeyCode::Esc if cancellable => break false,
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    // Restore terminal
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+    confirmed
+}
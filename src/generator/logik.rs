@@ -0,0 +1,233 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// BIP39 English wordlist, 2048 words = 11 bits of entropy per word
+pub const WORDLIST: &str = include_str!("wordlist.txt");
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.<>?/~";
+const LOOK_ALIKES: &str = "Il1O0o";
+
+pub const MIN_LENGTH: usize = 8;
+pub const MAX_LENGTH: usize = 128;
+pub const MIN_WORDS: usize = 3;
+pub const MAX_WORDS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Characters,
+    Passphrase,
+}
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub mode: Mode,
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub exclude_look_alikes: bool,
+    pub words: usize,
+    pub separator: char,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            mode: Mode::Characters,
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_look_alikes: true,
+            words: 6,
+            separator: '-',
+        }
+    }
+}
+
+impl Settings {
+    // Character sets of all enabled classes, look-alikes removed if requested.
+    // Lowercase if all are off, at least one class is required.
+    fn classes(&self) -> Vec<Vec<char>> {
+        let lowercase = self.lowercase || !(self.uppercase || self.digits || self.symbols);
+        [
+            (lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.digits, DIGITS),
+            (self.symbols, SYMBOLS),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, class)| {
+            class
+                .chars()
+                .filter(|c| !self.exclude_look_alikes || !LOOK_ALIKES.contains(*c))
+                .collect()
+        })
+        .collect()
+    }
+}
+
+pub fn generate(settings: &Settings) -> String {
+    match settings.mode {
+        Mode::Characters => generate_password(settings),
+        Mode::Passphrase => generate_passphrase(settings),
+    }
+}
+
+fn generate_password(settings: &Settings) -> String {
+    let mut rng = rand::rng();
+    let classes = settings.classes();
+    let charset: Vec<char> = classes.concat();
+
+    // One character of every enabled class, the rest from the whole set
+    let mut password: Vec<char> = classes
+        .iter()
+        .map(|class| class[rng.random_range(0..class.len())])
+        .collect();
+    while password.len() < settings.length {
+        password.push(charset[rng.random_range(0..charset.len())]);
+    }
+    password.truncate(settings.length);
+    password.shuffle(&mut rng);
+
+    password.into_iter().collect()
+}
+
+fn generate_passphrase(settings: &Settings) -> String {
+    let mut rng = rand::rng();
+    let words: Vec<&str> = WORDLIST.lines().collect();
+
+    (0..settings.words)
+        .map(|_| words[rng.random_range(0..words.len())])
+        .collect::<Vec<_>>()
+        .join(&settings.separator.to_string())
+}
+
+// Entropy of a generated secret in bits, based on the size of the set it was drawn from
+pub fn entropy_bits(settings: &Settings) -> f64 {
+    match settings.mode {
+        Mode::Characters => {
+            let charset_size: usize = settings.classes().iter().map(Vec::len).sum();
+            settings.length as f64 * (charset_size as f64).log2()
+        }
+        Mode::Passphrase => settings.words as f64 * (WORDLIST.lines().count() as f64).log2(),
+    }
+}
+
+pub fn strength_label(bits: f64) -> &'static str {
+    match bits {
+        b if b < 40.0 => "weak",
+        b if b < 60.0 => "fair",
+        b if b < 80.0 => "strong",
+        _ => "very strong",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn characters(length: usize) -> Settings {
+        Settings {
+            length,
+            ..Settings::default()
+        }
+    }
+
+    fn only(lowercase: bool, uppercase: bool, digits: bool, symbols: bool) -> Settings {
+        Settings {
+            lowercase,
+            uppercase,
+            digits,
+            symbols,
+            exclude_look_alikes: false,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn passwords_have_the_requested_length() {
+        for length in [MIN_LENGTH, 20, MAX_LENGTH] {
+            assert_eq!(generate(&characters(length)).chars().count(), length);
+        }
+    }
+
+    #[test]
+    fn every_enabled_class_appears() {
+        let classes = [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS];
+        // Short passwords leave little room, each class is still guaranteed
+        for _ in 0..100 {
+            let password = generate(&characters(MIN_LENGTH));
+            for class in classes {
+                assert!(password.chars().any(|c| class.contains(c)), "{}", password);
+            }
+            assert!(!password.chars().any(|c| LOOK_ALIKES.contains(c)));
+        }
+
+        let password = generate(&only(false, false, true, false));
+        assert!(password.chars().all(|c| DIGITS.contains(c)));
+        let password = generate(&only(false, true, false, true));
+        assert!(password
+            .chars()
+            .all(|c| UPPERCASE.contains(c) || SYMBOLS.contains(c)));
+    }
+
+    #[test]
+    fn all_classes_off_falls_back_to_lowercase() {
+        let settings = only(false, false, false, false);
+        let password = generate(&settings);
+        assert_eq!(password.len(), 20);
+        assert!(password.chars().all(|c| LOWERCASE.contains(c)));
+        assert_eq!(entropy_bits(&settings), 20.0 * 26f64.log2());
+    }
+
+    #[test]
+    fn passphrases_have_the_requested_words() {
+        let settings = Settings {
+            mode: Mode::Passphrase,
+            words: 4,
+            separator: '.',
+            ..Settings::default()
+        };
+        let passphrase = generate(&settings);
+        let words: Vec<&str> = passphrase.split('.').collect();
+        assert_eq!(words.len(), 4);
+        assert!(words
+            .iter()
+            .all(|word| WORDLIST.lines().any(|w| w == *word)));
+    }
+
+    #[test]
+    fn entropy_of_known_settings() {
+        assert_eq!(
+            entropy_bits(&only(false, false, true, false)),
+            20.0 * 10f64.log2()
+        );
+        assert_eq!(
+            entropy_bits(&only(true, true, true, true)),
+            20.0 * 89f64.log2()
+        );
+        // Without I, l, 1, O, 0 and o: 24 + 24 + 8 + 27 characters
+        assert_eq!(entropy_bits(&Settings::default()), 20.0 * 83f64.log2());
+
+        let passphrase = Settings {
+            mode: Mode::Passphrase,
+            ..Settings::default()
+        };
+        assert_eq!(entropy_bits(&passphrase), 66.0); // 6 words of 11 bits
+    }
+
+    #[test]
+    fn strength_labels() {
+        assert_eq!(strength_label(39.9), "weak");
+        assert_eq!(strength_label(40.0), "fair");
+        assert_eq!(strength_label(60.0), "strong");
+        assert_eq!(strength_label(80.0), "very strong");
+    }
+}
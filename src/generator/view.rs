@@ -0,0 +1,221 @@
+use super::logik::{self, Mode, Settings};
+use crate::lock::logik::IdleTimer;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Terminal,
+};
+use std::io;
+use zeroize::Zeroize;
+
+// Generator screen, called from the entry screens which already run in raw mode.
+// Returns the accepted password, None if the user pressed ESC or the vault has to be locked.
+pub fn draw_generator(idle_timer: &mut IdleTimer) -> Option<String> {
+    let stdout = io::stdout();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal.clear().unwrap();
+
+    let mut settings = Settings::default();
+    let mut password = logik::generate(&settings);
+    let mut state = ListState::default();
+    state.select(Some(0));
+
+    let result = loop {
+        let options = option_lines(&settings);
+        let bits = logik::entropy_bits(&settings);
+
+        terminal
+            .draw(|f| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(5), Constraint::Min(0)].as_ref())
+                    .split(f.area());
+
+                let preview = Paragraph::new(vec![
+                    Line::from(Span::styled(
+                        password.as_str(),
+                        Style::default()
+                            .fg(Color::Rgb(255, 163, 26))
+                            .add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(format!(
+                        "Entropy: {:.0} bits ({})",
+                        bits,
+                        logik::strength_label(bits)
+                    )),
+                ])
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                        .title("Password Generator")
+                        .title_style(Style::default().add_modifier(Modifier::BOLD)),
+                );
+                f.render_widget(preview, chunks[0]);
+
+                let list_items: Vec<ListItem> = options
+                    .iter()
+                    .map(|option| ListItem::new(option.as_str()))
+                    .collect();
+                let list = List::new(list_items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Options")
+                            .title_bottom(
+                                "Navigate (arrow keys), change (Space/Left/Right), new password (R), use (Enter), cancel (ESC)",
+                            ),
+                    )
+                    .highlight_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                    .highlight_symbol(">> ");
+                f.render_stateful_widget(list, chunks[1], &mut state);
+            })
+            .unwrap();
+
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => {
+                password.zeroize();
+                break None;
+            }
+        };
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                let selected = state.selected().unwrap_or(0);
+                let changed = match key.code {
+                    KeyCode::Up => {
+                        state.select(Some(selected.saturating_sub(1)));
+                        false
+                    }
+                    KeyCode::Down => {
+                        state.select(Some((selected + 1).min(options.len() - 1)));
+                        false
+                    }
+                    KeyCode::Left => adjust(&mut settings, selected, -1),
+                    KeyCode::Right => adjust(&mut settings, selected, 1),
+                    KeyCode::Char(' ') => adjust(&mut settings, selected, 0),
+                    KeyCode::Char('r') | KeyCode::Char('R') => true,
+                    KeyCode::Enter => break Some(password),
+                    KeyCode::Esc => {
+                        password.zeroize();
+                        break None;
+                    }
+                    _ => false,
+                };
+                if changed {
+                    password.zeroize();
+                    password = logik::generate(&settings);
+                }
+                // The option list is shorter in passphrase mode
+                let options_len = option_lines(&settings).len();
+                if state.selected().unwrap_or(0) >= options_len {
+                    state.select(Some(options_len - 1));
+                }
+            }
+        }
+    };
+
+    terminal.clear().unwrap();
+    result
+}
+
+fn option_lines(settings: &Settings) -> Vec<String> {
+    let check = |enabled: bool| if enabled { "[x]" } else { "[ ]" };
+    match settings.mode {
+        Mode::Characters => vec![
+            "Mode: characters".to_string(),
+            format!("Length: < {} >", settings.length),
+            format!("{} Lowercase (a-z)", check(settings.lowercase)),
+            format!("{} Uppercase (A-Z)", check(settings.uppercase)),
+            format!("{} Digits (0-9)", check(settings.digits)),
+            format!("{} Symbols (!@#...)", check(settings.symbols)),
+            format!(
+                "{} Exclude look-alikes (I l 1 O 0 o)",
+                check(settings.exclude_look_alikes)
+            ),
+        ],
+        Mode::Passphrase => vec![
+            "Mode: passphrase".to_string(),
+            format!("Words: < {} >", settings.words),
+            format!("Separator: < {} >", settings.separator),
+        ],
+    }
+}
+
+// Change the selected option, returns true if the password has to be regenerated
+fn adjust(settings: &mut Settings, option: usize, direction: i32) -> bool {
+    const SEPARATORS: [char; 5] = ['-', ' ', '.', '_', '+'];
+
+    match (settings.mode, option) {
+        (_, 0) => {
+            settings.mode = match settings.mode {
+                Mode::Characters => Mode::Passphrase,
+                Mode::Passphrase => Mode::Characters,
+            };
+        }
+        (Mode::Characters, 1) => {
+            settings.length = step(
+                settings.length,
+                direction,
+                logik::MIN_LENGTH,
+                logik::MAX_LENGTH,
+            );
+        }
+        (Mode::Characters, 2..=5) => {
+            let classes = [
+                settings.lowercase,
+                settings.uppercase,
+                settings.digits,
+                settings.symbols,
+            ];
+            // Keep at least one character class enabled
+            if classes[option - 2] && classes.iter().filter(|enabled| **enabled).count() == 1 {
+                return false;
+            }
+            let class = match option {
+                2 => &mut settings.lowercase,
+                3 => &mut settings.uppercase,
+                4 => &mut settings.digits,
+                _ => &mut settings.symbols,
+            };
+            *class = !*class;
+        }
+        (Mode::Characters, 6) => settings.exclude_look_alikes = !settings.exclude_look_alikes,
+        (Mode::Passphrase, 1) => {
+            settings.words = step(
+                settings.words,
+                direction,
+                logik::MIN_WORDS,
+                logik::MAX_WORDS,
+            );
+        }
+        (Mode::Passphrase, 2) => {
+            let current = SEPARATORS
+                .iter()
+                .position(|s| *s == settings.separator)
+                .unwrap_or(0);
+            let next = if direction < 0 {
+                (current + SEPARATORS.len() - 1) % SEPARATORS.len()
+            } else {
+                (current + 1) % SEPARATORS.len()
+            };
+            settings.separator = SEPARATORS[next];
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn step(value: usize, direction: i32, min: usize, max: usize) -> usize {
+    match direction {
+        d if d < 0 => value.saturating_sub(1).max(min),
+        d if d > 0 => (value + 1).min(max),
+        _ => value,
+    }
+}
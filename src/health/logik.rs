@@ -0,0 +1,320 @@
+use crate::breach;
+use crate::generator::logik::{self as generator, WORDLIST};
+use crate::menue::logik::{Entry, ItemType};
+use chrono::Utc;
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+
+// Most common passwords, most common first
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+// Passwords below are reported as weak
+pub const WEAK_BITS: f64 = 40.0;
+
+lazy_static! {
+    // Common passwords and the wordlist of the generator, with the rank of each word
+    static ref DICTIONARY: HashMap<&'static str, usize> = {
+        let mut dictionary = HashMap::new();
+        let words = COMMON_PASSWORDS.lines().chain(WORDLIST.lines());
+        for (rank, word) in words.enumerate() {
+            dictionary.entry(word).or_insert(rank + 1);
+        }
+        dictionary
+    };
+}
+
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+const MAX_WORD_LENGTH: usize = 16;
+// Longer passwords are only estimated on their start, the rest is counted as random
+const MAX_ANALYZED_LENGTH: usize = 64;
+
+// Findings for one login, entries without password are not checked
+#[derive(Debug)]
+pub struct EntryHealth {
+    pub index: usize, // Position of the entry in the vault
+    pub bits: f64,
+    pub reused: usize,         // Other entries with the same password
+    pub breached: u64,         // Times seen in breaches, 0 if unknown
+    pub age_days: Option<i64>, // Since the password was set, None if unknown
+    pub insecure_uris: usize,  // URIs starting with http://
+    pub missing_totp: bool,    // A website login without one-time codes
+}
+
+impl EntryHealth {
+    pub fn is_weak(&self) -> bool {
+        self.bits < WEAK_BITS
+    }
+
+    pub fn is_old(&self, max_age_days: u64) -> bool {
+        max_age_days > 0 && self.age_days.is_some_and(|age| age > max_age_days as i64)
+    }
+
+    pub fn issue_count(&self, max_age_days: u64) -> usize {
+        [
+            self.is_weak(),
+            self.reused > 0,
+            self.breached > 0,
+            self.is_old(max_age_days),
+            self.insecure_uris > 0,
+            self.missing_totp,
+        ]
+        .iter()
+        .filter(|issue| **issue)
+        .count()
+    }
+}
+
+// Check every login, entries with the most issues and weakest passwords first
+pub fn audit(entries: &[Entry], max_age_days: u64) -> Vec<EntryHealth> {
+    let logins: Vec<(usize, &Entry)> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.item_type == ItemType::Login && !entry.login.password.is_empty())
+        .collect();
+
+    let mut uses: HashMap<&str, usize> = HashMap::new();
+    for (_, entry) in &logins {
+        *uses.entry(entry.login.password.as_str()).or_default() += 1;
+    }
+
+    let mut report: Vec<EntryHealth> = logins
+        .iter()
+        .map(|(index, entry)| {
+            // The password was set when the previous one went into the history
+            let set = entry
+                .login
+                .password_history
+                .first()
+                .map(|old| old.last_used_date)
+                .or(entry.created);
+            EntryHealth {
+                index: *index,
+                bits: estimate_entropy(&entry.login.password),
+                reused: uses[entry.login.password.as_str()] - 1,
+                breached: breach::logik::breach_count(&entry.login.password).unwrap_or(0),
+                age_days: set.map(|set| (Utc::now() - set).num_days()),
+                insecure_uris: entry
+                    .login
+                    .uris
+                    .iter()
+                    .filter(|uri| uri.uri.to_lowercase().starts_with("http://"))
+                    .count(),
+                missing_totp: entry.login.totp.is_none() && !entry.login.uris.is_empty(),
+            }
+        })
+        .collect();
+
+    report.sort_by(|a, b| {
+        b.issue_count(max_age_days)
+            .cmp(&a.issue_count(max_age_days))
+            .then(a.bits.total_cmp(&b.bits))
+    });
+    report
+}
+
+pub fn strength_label(bits: f64) -> &'static str {
+    generator::strength_label(bits)
+}
+
+// Estimated entropy in bits, in the style of zxcvbn: the password is split into
+// dictionary words, repeats, sequences, keyboard walks, years and random characters,
+// and the cheapest split is what an attacker trying patterns first would need.
+pub fn estimate_entropy(password: &str) -> f64 {
+    let chars: Vec<char> = password.chars().collect();
+    let analyzed = chars.len().min(MAX_ANALYZED_LENGTH);
+    let random_bits = pool_size(&chars).log2();
+
+    // best[i] is the cheapest way to cover the first i characters
+    let mut best = vec![f64::INFINITY; analyzed + 1];
+    best[0] = 0.0;
+    for start in 0..analyzed {
+        if best[start].is_infinite() {
+            continue;
+        }
+        let single = best[start] + random_bits;
+        if single < best[start + 1] {
+            best[start + 1] = single;
+        }
+        for end in start + 3..=analyzed {
+            if let Some(bits) = pattern_bits(&chars[start..end]) {
+                // Every pattern costs a bit more, so random text is not split into many
+                let total = best[start] + bits + 1.0;
+                if total < best[end] {
+                    best[end] = total;
+                }
+            }
+        }
+    }
+
+    best[analyzed] + (chars.len() - analyzed) as f64 * random_bits
+}
+
+// Entropy of a token if it follows a known pattern
+fn pattern_bits(token: &[char]) -> Option<f64> {
+    [
+        dictionary_bits(token),
+        repeat_bits(token),
+        sequence_bits(token),
+        keyboard_bits(token),
+        year_bits(token),
+    ]
+    .into_iter()
+    .flatten()
+    .min_by(f64::total_cmp)
+}
+
+fn dictionary_bits(token: &[char]) -> Option<f64> {
+    if token.len() > MAX_WORD_LENGTH {
+        return None;
+    }
+    let lower: String = token.iter().flat_map(|c| c.to_lowercase()).collect();
+    let unleeted: String = lower.chars().map(unleet).collect();
+
+    let (rank, leet) = match DICTIONARY.get(lower.as_str()) {
+        Some(rank) => (*rank, false),
+        None => (*DICTIONARY.get(unleeted.as_str())?, true),
+    };
+
+    let mut bits = (rank as f64).log2().max(1.0);
+    // Capitalization and substitutions like p@ssw0rd only add little
+    let upper = token.iter().filter(|c| c.is_uppercase()).count();
+    if upper > 0 {
+        let first_only = upper == 1 && token[0].is_uppercase();
+        let all = upper == token.len();
+        bits += if first_only || all { 1.0 } else { upper as f64 };
+    }
+    if leet {
+        bits += 1.0;
+    }
+    Some(bits)
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        _ => c,
+    }
+}
+
+// aaaa, 1111
+fn repeat_bits(token: &[char]) -> Option<f64> {
+    token
+        .iter()
+        .all(|c| *c == token[0])
+        .then(|| pool_size(&token[..1]).log2() + (token.len() as f64).log2())
+}
+
+// abcd, 9876
+fn sequence_bits(token: &[char]) -> Option<f64> {
+    let step = token[1] as i64 - token[0] as i64;
+    if step.abs() != 1 || !token.windows(2).all(|w| w[1] as i64 - w[0] as i64 == step) {
+        return None;
+    }
+    let start_bits = if token[0].is_ascii_digit() {
+        10f64
+    } else {
+        26f64
+    }
+    .log2();
+    let descending = if step < 0 { 1.0 } else { 0.0 };
+    Some(start_bits + (token.len() as f64).log2() + descending)
+}
+
+// qwerty, asdf
+fn keyboard_bits(token: &[char]) -> Option<f64> {
+    if token.len() < 4 {
+        return None;
+    }
+    let lower: String = token.iter().flat_map(|c| c.to_lowercase()).collect();
+    let reversed: String = lower.chars().rev().collect();
+    KEYBOARD_ROWS
+        .iter()
+        .any(|row| row.contains(&lower) || row.contains(&reversed))
+        .then(|| (KEYBOARD_ROWS.concat().len() as f64).log2() + (token.len() as f64).log2())
+}
+
+// 1900 to 2039
+fn year_bits(token: &[char]) -> Option<f64> {
+    let text: String = token.iter().collect();
+    let year: u32 = text.parse().ok()?;
+    (token.len() == 4 && (1900..2040).contains(&year)).then(|| 140f64.log2())
+}
+
+// Size of the character classes the password draws from
+fn pool_size(chars: &[char]) -> f64 {
+    let classes: HashSet<u8> = chars
+        .iter()
+        .map(|c| match c {
+            'a'..='z' => 0,
+            'A'..='Z' => 1,
+            '0'..='9' => 2,
+            c if c.is_ascii() => 3,
+            _ => 4,
+        })
+        .collect();
+    classes
+        .iter()
+        .map(|class| match class {
+            0 | 1 => 26.0,
+            2 => 10.0,
+            3 => 33.0,
+            _ => 100.0,
+        })
+        .sum::<f64>()
+        .max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_bits(password: &str, expected: f64) {
+        let bits = estimate_entropy(password);
+        assert!((bits - expected).abs() < 1e-9, "{}: {}", password, bits);
+    }
+
+    #[test]
+    fn random_characters_count_their_pool() {
+        assert_bits("", 0.0);
+        assert_bits("Xk7#pQ2!vL9@", 12.0 * 95f64.log2());
+        assert_bits("ü", 100f64.log2());
+    }
+
+    #[test]
+    fn common_passwords_and_variations_are_weak() {
+        // Second most common password, every pattern costs one bit
+        assert_bits("password", 2.0);
+        assert_bits("Password", 3.0);
+        assert_bits("PASSWORD", 3.0);
+        assert_bits("P@ssw0rd", 4.0);
+    }
+
+    #[test]
+    fn patterns() {
+        assert_bits("aaaaaaaa", 26f64.log2() + 8f64.log2() + 1.0);
+        assert_bits("abcdef", 26f64.log2() + 6f64.log2() + 1.0);
+        assert_bits("zyxwvu", 26f64.log2() + 6f64.log2() + 1.0 + 1.0); // Descending
+        assert_bits("asdfgh", 36f64.log2() + 6f64.log2() + 1.0);
+        assert_bits("1987", 140f64.log2() + 1.0);
+    }
+
+    #[test]
+    fn combined_patterns_stay_below_random() {
+        let bits = estimate_entropy("correcthorse1987");
+        assert!(bits < WEAK_BITS, "{}", bits);
+        assert!(bits < 16.0 * 36f64.log2());
+        assert!(estimate_entropy("Tr0ub4dor&3") < estimate_entropy("Xk7#pQ2!vL9@"));
+    }
+
+    #[test]
+    fn only_the_start_of_long_passwords_is_analyzed() {
+        let password = "a".repeat(100);
+        let analyzed = 26f64.log2() + 64f64.log2() + 1.0;
+        assert_bits(&password, analyzed + 36.0 * 26f64.log2());
+    }
+}
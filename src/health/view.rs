@@ -0,0 +1,157 @@
+use super::logik::{self, EntryHealth};
+use crate::lock::logik::IdleTimer;
+use crate::menue::logik::Entry;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Terminal,
+};
+use std::io;
+
+// Show the vault health report, returns the entry to jump to.
+// None as well if the vault has to be locked, idle_timer tells.
+pub fn draw_report(
+    entries: &[Entry],
+    report: &[EntryHealth],
+    max_age_days: u64,
+    idle_timer: &mut IdleTimer,
+) -> Option<usize> {
+    let stdout = io::stdout();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal.clear().unwrap();
+
+    let count = |check: &dyn Fn(&EntryHealth) -> bool| report.iter().filter(|h| check(h)).count();
+    let summary = format!(
+        "{} logins checked: {} weak, {} reused, {} breached, {} older than {} days, {} with http:// URIs, {} without TOTP.",
+        report.len(),
+        count(&|h| h.is_weak()),
+        count(&|h| h.reused > 0),
+        count(&|h| h.breached > 0),
+        count(&|h| h.is_old(max_age_days)),
+        max_age_days,
+        count(&|h| h.insecure_uris > 0),
+        count(&|h| h.missing_totp),
+    );
+
+    let mut state = ListState::default();
+    state.select(if report.is_empty() { None } else { Some(0) });
+
+    let result = loop {
+        terminal
+            .draw(|f| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(4), Constraint::Min(0)].as_ref())
+                    .split(f.area());
+
+                let summary_box = Paragraph::new(summary.as_str())
+                    .wrap(Wrap { trim: true })
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                            .title("Vault Health")
+                            .title_style(Style::default().add_modifier(Modifier::BOLD)),
+                    );
+                f.render_widget(summary_box, chunks[0]);
+
+                let items: Vec<ListItem> = if report.is_empty() {
+                    vec![ListItem::new("The vault has no logins to check.")]
+                } else {
+                    report
+                        .iter()
+                        .map(|health| ListItem::new(finding_line(entries, health, max_age_days)))
+                        .collect()
+                };
+                let list = List::new(items)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Logins, most issues first")
+                            .title_bottom(
+                                "Open entry (Enter), navigate (arrow keys), return (ESC)",
+                            ),
+                    )
+                    .highlight_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                    .highlight_symbol(">> ");
+                f.render_stateful_widget(list, chunks[1], &mut state);
+            })
+            .unwrap();
+
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => break None,
+        };
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                let selected = state.selected().unwrap_or(0);
+                match key.code {
+                    KeyCode::Down if selected + 1 < report.len() => {
+                        state.select(Some(selected + 1))
+                    }
+                    KeyCode::Up if selected > 0 => state.select(Some(selected - 1)),
+                    KeyCode::Enter if !report.is_empty() => {
+                        break Some(report[selected].index);
+                    }
+                    KeyCode::Esc => break None,
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    terminal.clear().unwrap();
+    result
+}
+
+// Entry name and password strength followed by one tag per issue
+fn finding_line<'a>(entries: &'a [Entry], health: &EntryHealth, max_age_days: u64) -> Line<'a> {
+    let issue = Style::default().fg(Color::Red);
+    let hint = Style::default().fg(Color::Yellow);
+    let mut spans = vec![Span::raw(entries[health.index].name.as_str())];
+    spans.push(Span::styled(
+        format!(
+            " [{} password, {:.0} bits]",
+            logik::strength_label(health.bits),
+            health.bits
+        ),
+        if health.is_weak() {
+            issue
+        } else {
+            Style::default().fg(Color::DarkGray)
+        },
+    ));
+    if health.reused > 0 {
+        spans.push(Span::styled(
+            format!(" [reused in {} other entries]", health.reused),
+            issue,
+        ));
+    }
+    if health.breached > 0 {
+        spans.push(Span::styled(
+            format!(" [seen {} times in breaches]", health.breached),
+            issue,
+        ));
+    }
+    if health.is_old(max_age_days) {
+        spans.push(Span::styled(
+            format!(" [{} days old]", health.age_days.unwrap_or_default()),
+            hint,
+        ));
+    }
+    if health.insecure_uris > 0 {
+        spans.push(Span::styled(" [http://]", hint));
+    }
+    if health.missing_totp {
+        spans.push(Span::styled(
+            " [no TOTP]",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
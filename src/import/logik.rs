@@ -0,0 +1,891 @@
+use super::view;
+use crate::client::RsPassClient;
+use crate::error::PassHubError;
+use crate::export::{logik as export, view as export_view};
+use crate::lock::logik::IdleTimer;
+use crate::menue::api::Revision;
+use crate::menue::logik::{
+    self as menue, Card, Entry, Field, FieldType, Identity, ItemType, LinkedId, Login,
+    PasswordHistory, SecureNote, SshKey, Uri, UriMatch,
+};
+use crate::sync::{logik as sync, view as sync_view};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroize;
+
+// Name of imported entries without title, URL or username
+const UNNAMED: &str = "Unnamed";
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(std::io::Error),
+    Format(String),
+    PasswordRequired, // Encrypted PassHub export
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "The file could not be read: {}", e),
+            ImportError::Format(message) => write!(f, "{}", message),
+            ImportError::PasswordRequired => write!(f, "The export is password protected."),
+        }
+    }
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(e: std::io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+// Result of parsing an export before it is merged into the vault
+pub struct ImportPreview {
+    pub new_entries: Vec<Entry>,
+    pub duplicates: Vec<Entry>,
+    pub skipped: usize, // Items of a type PassHub does not know or with too long fields
+}
+
+// Ask for an export file, preview it and upload the merged vault. Returns true if uploaded.
+// Gives up once idle_timer expires, the caller has to lock the vault then.
+pub fn import(
+    client: &RsPassClient,
+    master_key: &str,
+    entries: &mut Vec<Entry>,
+    revision: &mut Revision,
+    idle_timer: &mut IdleTimer,
+) -> bool {
+    loop {
+        let path = match view::ask_for_path(idle_timer) {
+            Some(path) => path,
+            None => return false,
+        };
+
+        let result = match read_file(Path::new(&path), None) {
+            Err(ImportError::PasswordRequired) => {
+                match export_view::ask_for_password(false, idle_timer) {
+                    Some(mut password) => {
+                        let result = read_file(Path::new(&path), Some(&password));
+                        password.zeroize();
+                        result
+                    }
+                    None => return false,
+                }
+            }
+            result => result,
+        };
+        let mut preview = match result {
+            Ok((imported, skipped)) => deduplicate(entries, imported, skipped),
+            Err(e) => {
+                view::error(&e.to_string());
+                continue;
+            }
+        };
+
+        if preview.new_entries.is_empty() {
+            view::error("The file contains no new entries.");
+            return false;
+        }
+        if !view::confirm_preview(&preview, idle_timer) {
+            preview.new_entries.zeroize();
+            preview.duplicates.zeroize();
+            return false;
+        }
+
+        let imported = preview.new_entries.len();
+        let base = entries.clone();
+        entries.extend(preview.new_entries);
+        return match sync::save(
+            client,
+            master_key,
+            base,
+            entries,
+            revision,
+            &mut |conflict| sync_view::resolve_conflict(conflict, idle_timer),
+        ) {
+            Ok(()) => {
+                view::success(imported);
+                true
+            }
+            // Locked in the conflict prompt, the caller locks the vault
+            Err(_) if idle_timer.expired() => false,
+            Err(e) => {
+                view::error(&format!("{} The import has not been saved.", e));
+                false
+            }
+        };
+    }
+}
+
+// Detect the export format from extension and content, returns (entries, skipped items).
+// Encrypted PassHub exports need the export password.
+pub fn read_file(path: &Path, password: Option<&str>) -> Result<(Vec<Entry>, usize), ImportError> {
+    let content = fs::read_to_string(path)?;
+    let content = content.trim_start_matches('\u{feff}'); // Byte order mark
+
+    if export::is_encrypted_export(content) {
+        let password = password.ok_or(ImportError::PasswordRequired)?;
+        let mut cleartext = export::decrypt_export(content, password).map_err(|e| match e {
+            PassHubError::Decryption => {
+                ImportError::Format("Wrong password or damaged file".to_string())
+            }
+            e => ImportError::Format(e.to_string()),
+        })?;
+        let result = parse_bitwarden_json(&cleartext);
+        cleartext.zeroize();
+        return result;
+    }
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match extension.as_str() {
+        "json" => parse_bitwarden_json(content),
+        "xml" => parse_keepass_xml(content),
+        "csv" => parse_csv(content),
+        _ if content.trim_start().starts_with('{') => parse_bitwarden_json(content),
+        _ if content.trim_start().starts_with('<') => parse_keepass_xml(content),
+        _ => parse_csv(content),
+    }
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BitwardenItem {
+    #[serde(rename = "type", default = "login_type")]
+    item_type: u8,
+    name: String,
+    #[serde(rename = "folderId")]
+    folder_id: Option<String>,
+    #[serde(default)]
+    favorite: bool,
+    notes: Option<String>,
+    login: Option<BitwardenLogin>,
+    #[serde(rename = "secureNote")]
+    secure_note: Option<SecureNote>,
+    card: Option<Card>,
+    identity: Option<Identity>,
+    #[serde(rename = "sshKey")]
+    ssh_key: Option<SshKey>,
+    #[serde(rename = "passwordHistory")]
+    password_history: Option<Vec<PasswordHistory>>,
+    #[serde(rename = "creationDate")]
+    creation_date: Option<DateTime<Utc>>,
+    #[serde(rename = "revisionDate")]
+    revision_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    fields: Option<Vec<BitwardenField>>,
+    // Only in exports written by PassHub
+    #[serde(rename = "passhubTags", default)]
+    tags: Vec<String>,
+    #[serde(rename = "passhubLastUsedDate")]
+    last_used: Option<DateTime<Utc>>,
+}
+
+fn login_type() -> u8 {
+    1
+}
+
+#[derive(Deserialize)]
+struct BitwardenField {
+    name: Option<String>,
+    value: Option<String>,
+    #[serde(rename = "type")]
+    field_type: u8,
+    #[serde(rename = "linkedId")]
+    linked_id: Option<u16>,
+}
+
+impl BitwardenField {
+    // Linked fields of cards and identities have no login value to refer to
+    fn into_field(self) -> Option<Field> {
+        let (field_type, linked_id) = match (self.field_type, self.linked_id) {
+            (0, _) => (FieldType::Text, None),
+            (1, _) => (FieldType::Hidden, None),
+            (2, _) => (FieldType::Boolean, None),
+            (3, Some(100)) => (FieldType::Linked, Some(LinkedId::Username)),
+            (3, Some(101)) => (FieldType::Linked, Some(LinkedId::Password)),
+            _ => return None,
+        };
+        Some(Field {
+            name: self.name.unwrap_or_default(),
+            value: if field_type == FieldType::Linked {
+                None
+            } else {
+                self.value
+            },
+            field_type,
+            linked_id,
+        })
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct BitwardenLogin {
+    #[serde(default)]
+    uris: Option<Vec<BitwardenUri>>,
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+    #[serde(rename = "match")]
+    match_type: Option<UriMatch>,
+}
+
+pub fn parse_bitwarden_json(content: &str) -> Result<(Vec<Entry>, usize), ImportError> {
+    let export: BitwardenExport = serde_json::from_str(content)
+        .map_err(|e| ImportError::Format(format!("Invalid Bitwarden export: {}", e)))?;
+    if export.encrypted {
+        return Err(ImportError::Format(
+            "Encrypted Bitwarden exports are not supported, please export unencrypted JSON."
+                .to_string(),
+        ));
+    }
+
+    let mut skipped = 0;
+    let mut entries = Vec::new();
+    for item in export.items {
+        let folder = export
+            .folders
+            .iter()
+            .find(|folder| Some(&folder.id) == item.folder_id.as_ref())
+            .map(|folder| folder.name.clone());
+        let item_type = match item.item_type {
+            1 => ItemType::Login,
+            2 => ItemType::SecureNote,
+            3 => ItemType::Card,
+            4 => ItemType::Identity,
+            5 => ItemType::SshKey,
+            _ => {
+                skipped += 1; // Types added to Bitwarden later
+                continue;
+            }
+        };
+        let fields = item
+            .fields
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(BitwardenField::into_field)
+            .collect();
+        // Dates missing in the export are set to the time of the import
+        let now = Entry::new(item_type);
+        let created = item.creation_date.or(now.created);
+        let modified = item.revision_date.or(now.modified);
+        if item_type != ItemType::Login {
+            let mut entry = Entry {
+                name: item.name,
+                notes: item.notes,
+                fields,
+                folder,
+                tags: item.tags,
+                favorite: item.favorite,
+                created,
+                modified,
+                last_used: item.last_used,
+                ..now
+            };
+            entry.secure_note = item.secure_note.or(entry.secure_note);
+            entry.card = item.card.or(entry.card);
+            entry.identity = item.identity.or(entry.identity);
+            entry.ssh_key = item.ssh_key.or(entry.ssh_key);
+            if has_too_long_fields(&entry) {
+                skipped += 1;
+                continue;
+            }
+            entries.push(entry);
+            continue;
+        }
+
+        let login = item.login.unwrap_or_default();
+        let uris = login
+            .uris
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|uri| {
+                uri.uri.map(|value| Uri {
+                    uri: value,
+                    match_type: uri.match_type,
+                })
+            })
+            .collect();
+        let mut entry = new_entry(
+            item.name,
+            uris,
+            login.username,
+            login.password.unwrap_or_default(),
+            login.totp,
+            item.notes,
+        );
+        entry.fields = fields;
+        entry.folder = folder;
+        entry.login.password_history = item.password_history.unwrap_or_default();
+        entry.tags = item.tags;
+        entry.favorite = item.favorite;
+        entry.created = created;
+        entry.modified = modified;
+        entry.last_used = item.last_used;
+        if has_too_long_fields(&entry) {
+            skipped += 1;
+            continue;
+        }
+        entries.push(entry);
+    }
+    Ok((entries, skipped))
+}
+
+// Returns (entries, skipped entries)
+pub fn parse_keepass_xml(content: &str) -> Result<(Vec<Entry>, usize), ImportError> {
+    let document = roxmltree::Document::parse(content)
+        .map_err(|e| ImportError::Format(format!("Invalid KeePass XML: {}", e)))?;
+    let root = document
+        .descendants()
+        .find(|node| node.has_tag_name("Root"))
+        .ok_or_else(|| ImportError::Format("Not a KeePass 2 XML export.".to_string()))?;
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for node in root.descendants().filter(|node| node.has_tag_name("Entry")) {
+        // Old versions of an entry are stored as nested entries inside <History>
+        if node.ancestors().any(|a| a.has_tag_name("History")) {
+            continue;
+        }
+
+        let mut title = String::new();
+        let mut username = None;
+        let mut password = String::new();
+        let mut notes = None;
+        let mut totp = None;
+        let mut uris = Vec::new();
+        let mut fields = Vec::new();
+        for string in node.children().filter(|child| child.has_tag_name("String")) {
+            let key = child_text(&string, "Key");
+            let value = child_text(&string, "Value");
+            let protected = string
+                .children()
+                .find(|child| child.has_tag_name("Value"))
+                .and_then(|child| child.attribute("ProtectInMemory"))
+                .is_some_and(|p| p.eq_ignore_ascii_case("true"));
+            if value.is_empty() {
+                continue;
+            }
+            match key.as_str() {
+                "Title" => title = value,
+                "UserName" => username = Some(value),
+                "Password" => password = value,
+                "Notes" => notes = Some(value),
+                "URL" => uris.insert(0, Uri::new(value)),
+                "otp" => totp = Some(value),
+                "TimeOtp-Secret-Base32" if totp.is_none() => totp = Some(value),
+                key if key.starts_with("KP2A_URL") => uris.push(Uri::new(value)),
+                key if key.starts_with("TimeOtp-") => {}
+                _ => fields.push(Field {
+                    name: key,
+                    value: Some(value),
+                    field_type: if protected {
+                        FieldType::Hidden
+                    } else {
+                        FieldType::Text
+                    },
+                    linked_id: None,
+                }),
+            }
+        }
+
+        if title.is_empty() {
+            title = uris
+                .first()
+                .map(|uri| uri_name(&uri.uri))
+                .or_else(|| username.clone())
+                .unwrap_or_else(|| UNNAMED.to_string());
+        }
+        let mut entry = new_entry(title, uris, username, password, totp, notes);
+        entry.fields = fields;
+        // The first group is the database itself, the ones below become folders
+        let groups: Vec<String> = node
+            .ancestors()
+            .filter(|a| a.has_tag_name("Group"))
+            .map(|group| child_text(&group, "Name"))
+            .collect();
+        entry.folder = Some(
+            groups
+                .iter()
+                .rev()
+                .skip(1)
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join("/"),
+        );
+        if let Some(times) = node.children().find(|child| child.has_tag_name("Times")) {
+            entry.created = keepass_time(&times, "CreationTime").or(entry.created);
+            entry.modified = keepass_time(&times, "LastModificationTime").or(entry.modified);
+        }
+        entry.tags = child_text(&node, "Tags")
+            .split([';', ','])
+            .map(|tag| tag.to_string())
+            .collect();
+        menue::normalize_folder_and_tags(&mut entry);
+        if has_too_long_fields(&entry) {
+            skipped += 1;
+            continue;
+        }
+        entries.push(entry);
+    }
+    Ok((entries, skipped))
+}
+
+// Same limit as entering the fields by hand, cutting a password off would break it
+fn has_too_long_fields(entry: &Entry) -> bool {
+    let login = &entry.login;
+    [&entry.name, &login.password]
+        .into_iter()
+        .chain(&entry.notes)
+        .chain(&entry.folder)
+        .chain(&login.username)
+        .chain(login.uris.iter().map(|uri| &uri.uri))
+        .any(|value| !menue::validate_string_length(value))
+}
+
+// KeePass 2.x writes ISO dates, KDBX 4 exports Base64 seconds since year 1
+fn keepass_time(times: &roxmltree::Node, tag: &str) -> Option<DateTime<Utc>> {
+    let text = child_text(times, tag);
+    if let Ok(date) = DateTime::parse_from_rfc3339(&text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let bytes: [u8; 8] = STANDARD.decode(&text).ok()?.try_into().ok()?;
+    let seconds = i64::from_le_bytes(bytes) - KEEPASS_EPOCH_OFFSET;
+    DateTime::from_timestamp(seconds, 0)
+}
+
+// Seconds between 0001-01-01 and 1970-01-01
+const KEEPASS_EPOCH_OFFSET: i64 = 62135596800;
+
+fn child_text(node: &roxmltree::Node, tag: &str) -> String {
+    node.children()
+        .find(|child| child.has_tag_name(tag))
+        .and_then(|child| child.text())
+        .unwrap_or("")
+        .to_string()
+}
+
+// Chrome (name,url,username,password,note), Firefox (url,username,password,...),
+// Bitwarden and KeePass 2/KeePassXC CSV exports are told apart by their header.
+// Returns (entries, skipped rows).
+pub fn parse_csv(content: &str) -> Result<(Vec<Entry>, usize), ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| ImportError::Format(format!("Invalid CSV export: {}", e)))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let name_column = column(&["name", "title", "account"]);
+    let url_column = column(&["url", "login_uri", "uri", "web site"]);
+    let username_column = column(&["username", "login_username", "login name"]);
+    let password_column = column(&["password", "login_password"]);
+    let notes_column = column(&["note", "notes", "comments"]);
+    let totp_column = column(&["totp", "login_totp"]);
+    let type_column = column(&["type"]);
+    let folder_column = column(&["folder", "grouping"]);
+    let group_column = column(&["group"]); // KeePassXC, starts with the database itself
+    let favorite_column = column(&["favorite"]);
+
+    let password_column = password_column.ok_or_else(|| {
+        ImportError::Format("Unknown CSV format, no password column found.".to_string())
+    })?;
+
+    let imported = [
+        name_column,
+        url_column,
+        username_column,
+        Some(password_column),
+        notes_column,
+        folder_column,
+        group_column,
+    ];
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for record in reader.records() {
+        let record = record.map_err(|e| ImportError::Format(format!("Invalid CSV row: {}", e)))?;
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|c| record.get(c))
+                .map(|value| value.to_string())
+                .filter(|value| !value.is_empty())
+        };
+        // Same limit as entering the fields by hand, cutting a password off would break it
+        if imported
+            .iter()
+            .filter_map(|column| field(*column))
+            .any(|value| !menue::validate_string_length(&value))
+        {
+            skipped += 1;
+            continue;
+        }
+        let folder = field(folder_column).or_else(|| {
+            field(group_column)
+                .and_then(|group| group.split_once('/').map(|(_, folder)| folder.to_string()))
+        });
+
+        // Bitwarden CSV also contains secure notes, they only have a name and notes
+        match field(type_column).as_deref() {
+            None | Some("login") => {}
+            Some("note") => {
+                entries.push(Entry {
+                    name: field(name_column).unwrap_or_else(|| UNNAMED.to_string()),
+                    notes: field(notes_column),
+                    folder,
+                    favorite: field(favorite_column).as_deref() == Some("1"),
+                    ..Entry::new(ItemType::SecureNote)
+                });
+                continue;
+            }
+            Some(_) => {
+                skipped += 1;
+                continue;
+            }
+        }
+
+        // Bitwarden CSV lists several URIs separated by commas
+        let uris: Vec<Uri> = field(url_column)
+            .map(|urls| {
+                urls.split(',')
+                    .map(|u| Uri::new(u.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let name = field(name_column)
+            .or_else(|| uris.first().map(|uri| uri_name(&uri.uri)))
+            .or_else(|| field(username_column))
+            .unwrap_or_else(|| UNNAMED.to_string());
+
+        let mut entry = new_entry(
+            name,
+            uris,
+            field(username_column),
+            field(Some(password_column)).unwrap_or_default(),
+            field(totp_column),
+            field(notes_column),
+        );
+        entry.folder = folder;
+        // Bitwarden writes 1 for favorites
+        entry.favorite = field(favorite_column).as_deref() == Some("1");
+        menue::normalize_folder_and_tags(&mut entry);
+        entries.push(entry);
+    }
+    Ok((entries, skipped))
+}
+
+fn new_entry(
+    name: String,
+    uris: Vec<Uri>,
+    username: Option<String>,
+    password: String,
+    totp: Option<String>,
+    notes: Option<String>,
+) -> Entry {
+    let uris = uris.into_iter().filter(|uri| !uri.uri.is_empty()).collect();
+
+    Entry {
+        name,
+        notes,
+        login: Login {
+            uris,
+            username,
+            password,
+            totp,
+            password_history: Vec::new(),
+        },
+        ..Entry::new(ItemType::Login)
+    }
+}
+
+// Name an entry without title after the host of its URI
+fn uri_name(uri: &str) -> String {
+    url::Url::parse(uri)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_else(|| uri.to_string())
+}
+
+// Split imported entries into new ones and ones the vault (or the file) already contains
+pub fn deduplicate(existing: &[Entry], imported: Vec<Entry>, skipped: usize) -> ImportPreview {
+    let mut seen: HashSet<(String, Option<String>, String)> =
+        existing.iter().map(duplicate_key).collect();
+    let mut preview = ImportPreview {
+        new_entries: Vec::new(),
+        duplicates: Vec::new(),
+        skipped,
+    };
+
+    for entry in imported {
+        if seen.insert(duplicate_key(&entry)) {
+            preview.new_entries.push(entry);
+        } else {
+            preview.duplicates.push(entry);
+        }
+    }
+    preview
+}
+
+// Entries are the same login if host, username and password match,
+// other items if name and content match
+fn duplicate_key(entry: &Entry) -> (String, Option<String>, String) {
+    if let Some(payload) = entry.payload() {
+        let mut content: Vec<&str> = payload
+            .values()
+            .into_iter()
+            .map(|value| value.as_deref().unwrap_or_default())
+            .collect();
+        content.push(entry.notes.as_deref().unwrap_or_default());
+        return (entry.name.to_lowercase(), None, content.join("\n"));
+    }
+    let host = entry
+        .login
+        .uris
+        .first()
+        .map(|uri| uri_name(&uri.uri).to_lowercase())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| entry.name.to_lowercase());
+    (
+        host,
+        entry.login.username.clone(),
+        entry.login.password.clone(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keepass_2_csv_columns() {
+        let csv = "\"Account\",\"Login Name\",\"Password\",\"Web Site\",\"Comments\"\n\
+                   \"Mail\",\"alice\",\"secret\",\"https://mail.example.com\",\"Work account\"\n";
+        let (entries, skipped) = parse_csv(csv).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(entries[0].name, "Mail");
+        assert_eq!(entries[0].login.username.as_deref(), Some("alice"));
+        assert_eq!(entries[0].login.password, "secret");
+        assert_eq!(entries[0].login.uris[0].uri, "https://mail.example.com");
+        assert_eq!(entries[0].notes.as_deref(), Some("Work account"));
+    }
+
+    #[test]
+    fn keepassxc_csv_groups_become_folders() {
+        let csv = "\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\"\n\
+                   \"Root/Email/Work\",\"Mail\",\"alice\",\"secret\",\"\",\"\"\n\
+                   \"Root\",\"\",\"bob\",\"secret\",\"\",\"\"\n";
+        let (entries, _) = parse_csv(csv).unwrap();
+        assert_eq!(entries[0].folder.as_deref(), Some("Email/Work"));
+        assert_eq!(entries[1].folder, None);
+        assert_eq!(entries[1].name, "bob"); // No title or URL
+    }
+
+    #[test]
+    fn csv_rows_with_too_long_fields_are_skipped() {
+        let csv = format!(
+            "name,username,password\nok,alice,secret\nlong,bob,{}\n,,secret\n",
+            "x".repeat(201)
+        );
+        let (entries, skipped) = parse_csv(&csv).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].name, UNNAMED);
+    }
+
+    #[test]
+    fn minimal_bitwarden_json() {
+        let json = r#"{
+            "encrypted": false,
+            "folders": [{ "id": "f1", "name": "Email" }],
+            "items": [
+                {
+                    "type": 1,
+                    "name": "Mail",
+                    "folderId": "f1",
+                    "login": {
+                        "username": "alice",
+                        "password": "secret",
+                        "uris": [{ "uri": "https://mail.example.com", "match": null }]
+                    }
+                },
+                { "type": 2, "name": "Wifi", "notes": "Router in the hall" },
+                { "type": 99, "name": "Passkey" }
+            ]
+        }"#;
+        let (entries, skipped) = parse_bitwarden_json(json).unwrap();
+        assert_eq!(skipped, 1); // Unknown item type
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "Mail");
+        assert_eq!(entries[0].folder.as_deref(), Some("Email"));
+        assert_eq!(entries[0].login.username.as_deref(), Some("alice"));
+        assert_eq!(entries[0].login.password, "secret");
+        assert_eq!(entries[0].login.uris[0].uri, "https://mail.example.com");
+        assert_eq!(entries[1].item_type, ItemType::SecureNote);
+        assert_eq!(entries[1].notes.as_deref(), Some("Router in the hall"));
+    }
+
+    #[test]
+    fn bitwarden_items_with_too_long_fields_are_skipped() {
+        let json = format!(
+            r#"{{ "items": [
+                {{ "type": 1, "name": "ok", "login": {{ "password": "secret" }} }},
+                {{ "type": 1, "name": "long", "login": {{ "password": "{0}" }} }},
+                {{ "type": 2, "name": "note", "notes": "{0}" }}
+            ] }}"#,
+            "x".repeat(201)
+        );
+        let (entries, skipped) = parse_bitwarden_json(&json).unwrap();
+        assert_eq!(skipped, 2);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "ok");
+    }
+
+    const KEEPASS_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<KeePassFile>
+  <Root>
+    <Group>
+      <Name>Database</Name>
+      <Entry>
+        <String><Key>Title</Key><Value>Bank</Value></String>
+        <String><Key>Password</Key><Value>secret</Value></String>
+      </Entry>
+      <Group>
+        <Name>Email</Name>
+        <Group>
+          <Name>Work</Name>
+          <Entry>
+            <String><Key>Title</Key><Value>Mail</Value></String>
+            <String><Key>UserName</Key><Value>alice</Value></String>
+            <String><Key>Password</Key><Value>secret</Value></String>
+            <String><Key>URL</Key><Value>https://mail.example.com</Value></String>
+            <String><Key>PIN</Key><Value ProtectInMemory="True">1234</Value></String>
+            <History>
+              <Entry>
+                <String><Key>Title</Key><Value>Old mail</Value></String>
+              </Entry>
+            </History>
+          </Entry>
+          <Entry>
+            <String><Key>URL</Key><Value>https://shop.example.com/login</Value></String>
+            <String><Key>Password</Key><Value>secret</Value></String>
+          </Entry>
+          <Entry>
+            <String><Key>UserName</Key><Value>bob</Value></String>
+            <String><Key>Password</Key><Value>secret</Value></String>
+          </Entry>
+          <Entry>
+            <String><Key>Password</Key><Value>secret</Value></String>
+          </Entry>
+        </Group>
+      </Group>
+    </Group>
+  </Root>
+</KeePassFile>"#;
+
+    #[test]
+    fn keepass_xml_groups_become_folders() {
+        let (entries, skipped) = parse_keepass_xml(KEEPASS_XML).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(entries.len(), 5); // History entries are not imported
+        assert_eq!(entries[0].name, "Bank");
+        assert_eq!(entries[0].folder, None);
+        assert_eq!(entries[1].name, "Mail");
+        assert_eq!(entries[1].folder.as_deref(), Some("Email/Work"));
+        assert_eq!(entries[1].login.username.as_deref(), Some("alice"));
+        assert_eq!(entries[1].login.uris[0].uri, "https://mail.example.com");
+        assert_eq!(entries[1].fields[0].name, "PIN");
+        assert_eq!(entries[1].fields[0].field_type, FieldType::Hidden);
+    }
+
+    #[test]
+    fn keepass_xml_entries_without_title_are_named_like_csv() {
+        let (entries, _) = parse_keepass_xml(KEEPASS_XML).unwrap();
+        assert_eq!(entries[2].name, "shop.example.com");
+        assert_eq!(entries[3].name, "bob");
+        assert_eq!(entries[4].name, UNNAMED);
+    }
+
+    #[test]
+    fn keepass_xml_entries_with_too_long_fields_are_skipped() {
+        let xml = KEEPASS_XML.replace(
+            "<Value>bob</Value>",
+            &format!("<Value>{}</Value>", "x".repeat(201)),
+        );
+        let (entries, skipped) = parse_keepass_xml(&xml).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(entries.len(), 4);
+    }
+
+    #[test]
+    fn malformed_input_is_a_format_error() {
+        let format_error = |result: Result<(Vec<Entry>, usize), ImportError>| {
+            matches!(result, Err(ImportError::Format(_)))
+        };
+        assert!(format_error(parse_bitwarden_json("{ \"items\": [")));
+        assert!(format_error(parse_bitwarden_json("{ \"items\": 1 }")));
+        assert!(format_error(parse_bitwarden_json(
+            "{ \"encrypted\": true, \"items\": [] }"
+        )));
+        assert!(format_error(parse_keepass_xml("<KeePassFile><Root>")));
+        assert!(format_error(parse_keepass_xml("<Other></Other>")));
+        assert!(format_error(parse_csv("name,username\nMail,alice\n")));
+    }
+
+    #[test]
+    fn encrypted_export_keeps_tags_and_last_used() {
+        let mut entry = new_entry(
+            "Mail".to_string(),
+            Vec::new(),
+            Some("alice".to_string()),
+            "secret".to_string(),
+            None,
+            None,
+        );
+        entry.tags = vec!["work".to_string(), "finance".to_string()];
+        entry.last_used = entry.modified;
+        let content = export::to_bitwarden_json(std::slice::from_ref(&entry)).unwrap();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(
+            file.path(),
+            export::encrypt_export(&content, "Export-Pass-1").unwrap(),
+        )
+        .unwrap();
+
+        let (entries, skipped) = read_file(file.path(), Some("Export-Pass-1")).unwrap();
+        assert_eq!(skipped, 0);
+        assert_eq!(entries[0].tags, entry.tags);
+        assert_eq!(entries[0].last_used, entry.last_used);
+        assert_eq!(entries[0].login.password, "secret");
+    }
+}
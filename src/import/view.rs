@@ -0,0 +1,208 @@
+use super::logik::ImportPreview;
+use crate::lock::logik::IdleTimer;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    Terminal,
+};
+use std::io;
+
+// Screen that asks for the export file, None if the user pressed ESC or the vault has to be locked
+pub fn ask_for_path(idle_timer: &mut IdleTimer) -> Option<String> {
+    // Setup terminal
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    let mut input = String::new(); // Stores user input
+
+    let result = loop {
+        terminal
+            .draw(|frame| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(80), Constraint::Percentage(20)].as_ref())
+                    .split(frame.area());
+
+                let text = Paragraph::new(
+                    "Please enter the path of the file to import and press Enter.\n\nSupported formats:\n- Encrypted PassHub export (.json)\n- Bitwarden unencrypted JSON export (.json)\n- KeePass 2 XML export (.xml)\n- Chrome, Firefox or Bitwarden CSV export (.csv)",
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                        .title("Import")
+                        .title_style(Style::default().add_modifier(Modifier::BOLD))
+                        .title_bottom("Import (Enter), cancel (ESC)"),
+                );
+
+                let input_box = Paragraph::new(input.as_str())
+                    .block(Block::default().borders(Borders::ALL).title("File"));
+
+                frame.render_widget(text, chunks[0]);
+                frame.render_widget(input_box, chunks[1]);
+            })
+            .unwrap();
+
+        // Handle user input
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => break None,
+        };
+        if let Event::Key(key_event) = event {
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Enter if !input.trim().is_empty() => {
+                        break Some(input.trim().to_string());
+                    }
+                    KeyCode::Esc => break None,
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+    result
+}
+
+// Show what will be imported, returns true if the user confirms
+pub fn confirm_preview(preview: &ImportPreview, idle_timer: &mut IdleTimer) -> bool {
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    let result = loop {
+        terminal
+            .draw(|frame| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(4), Constraint::Min(0)].as_ref())
+                    .split(frame.area());
+
+                let summary = Paragraph::new(format!(
+                    "{} new entries will be added. {} duplicates and {} unsupported or too long items are skipped.",
+                    preview.new_entries.len(),
+                    preview.duplicates.len(),
+                    preview.skipped
+                ))
+                .wrap(Wrap { trim: true })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                        .title("Import Preview")
+                        .title_style(Style::default().add_modifier(Modifier::BOLD)),
+                );
+
+                let list_items: Vec<ListItem> = preview
+                    .new_entries
+                    .iter()
+                    .map(|entry| {
+                        ListItem::new(format!(
+                            "{}  {}  {}",
+                            entry.name,
+                            entry.login.username.as_deref().unwrap_or("(none)"),
+                            entry
+                                .login
+                                .uris
+                                .first()
+                                .map(|uri| uri.uri.as_str())
+                                .unwrap_or("")
+                        ))
+                    })
+                    .collect();
+                let list = List::new(list_items).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("New Entries")
+                        .title_bottom("Import (Enter), cancel (ESC)"),
+                );
+
+                frame.render_widget(summary, chunks[0]);
+                frame.render_widget(list, chunks[1]);
+            })
+            .unwrap();
+
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => break false,
+        };
+        if let Event::Key(key_event) = event {
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Enter => break true,
+                    KeyCode::Esc => break false,
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+    result
+}
+
+pub fn success(count: usize) {
+    message_screen(
+        "Import",
+        &format!(
+            "{} entries have been imported. \nPress Enter to continue.",
+            count
+        ),
+    );
+}
+
+pub fn error(message: &str) {
+    message_screen("Error", &format!("{} \nPress Enter to continue.", message));
+}
+
+fn message_screen(title: &str, message: &str) {
+    // Setup terminal for message screen
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    loop {
+        terminal
+            .draw(|frame| {
+                let size = frame.area();
+                let block = Block::default().borders(Borders::ALL).title(title);
+
+                let paragraph = Paragraph::new(message).block(block);
+
+                frame.render_widget(paragraph, size);
+            })
+            .unwrap();
+
+        // Wait for user input to dismiss the screen
+        if let Event::Key(key_event) = event::read().unwrap() {
+            if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Enter {
+                break;
+            }
+        }
+    }
+
+    // Restore terminal
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+}
@@ -0,0 +1,119 @@
+use super::view;
+use crate::login::kdf;
+use crate::login::logik as login;
+use crossterm::event::{self, Event, KeyEventKind};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
+
+// While the vault is locked only a salted hash of the master key stays in memory
+pub struct Verifier {
+    salt: [u8; 16],
+    hash: [u8; 32],
+}
+
+impl Verifier {
+    pub fn new(master_key: &str) -> Self {
+        let mut salt = [0u8; 16];
+        rand::rng().fill(&mut salt);
+        Verifier {
+            salt,
+            hash: hash(&salt, master_key),
+        }
+    }
+
+    pub fn matches(&self, master_key: &str) -> bool {
+        // Compare without an early exit
+        hash(&self.salt, master_key)
+            .iter()
+            .zip(self.hash.iter())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+}
+
+fn hash(salt: &[u8], master_key: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(master_key.as_bytes());
+    hasher.finalize().into()
+}
+
+// How often screens waiting for input check the idle time
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Tracks the time since the last key press, a timeout of 0 never locks.
+// Screens opened from the entry list share the timer, so any of them can lock the vault.
+pub struct IdleTimer {
+    timeout: Option<Duration>,
+    last_activity: Instant,
+    locked: bool, // Ctrl+L pressed
+}
+
+impl IdleTimer {
+    pub fn new(timeout_seconds: u64) -> Self {
+        IdleTimer {
+            timeout: (timeout_seconds > 0).then(|| Duration::from_secs(timeout_seconds)),
+            last_activity: Instant::now(),
+            locked: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    // Lock without waiting for the timeout
+    pub fn lock_now(&mut self) {
+        self.locked = true;
+    }
+
+    pub fn expired(&self) -> bool {
+        self.locked
+            || self
+                .timeout
+                .is_some_and(|timeout| self.last_activity.elapsed() >= timeout)
+    }
+
+    // Wait for the next terminal event, None once the vault has to be locked
+    pub fn next_event(&mut self) -> Option<Event> {
+        loop {
+            if self.expired() {
+                return None;
+            }
+            if event::poll(POLL_INTERVAL).unwrap() {
+                let event = event::read().unwrap();
+                if matches!(&event, Event::Key(key) if key.kind == KeyEventKind::Press) {
+                    self.reset();
+                }
+                return Some(event);
+            }
+        }
+    }
+}
+
+// Show the lock screen until the right master password is entered.
+// Returns the master key, None if the user chose to log out instead.
+pub fn unlock(verifier: &Verifier) -> Option<String> {
+    let email = login::get_mail_from_storage();
+    let mut wrong_password = false;
+
+    loop {
+        let mut password = view::draw_lock_screen(&email, wrong_password)?;
+        let master_key = login::hash_argon_2_master_key(&password, &email, &kdf::load(&email));
+        password.zeroize();
+
+        match master_key {
+            Ok(master_key) if verifier.matches(&master_key) => return Some(master_key),
+            Ok(mut master_key) => {
+                master_key.zeroize();
+                wrong_password = true;
+            }
+            Err(_) => {
+                view::error_argon2_fail();
+                return None;
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use super::logik::IdleTimer;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Terminal,
+};
+use std::io::{self, stdout};
+use zeroize::Zeroize;
+
+// Returns the entered master password, None if the user pressed ESC to log out
+pub fn draw_lock_screen(email: &str, wrong_password: bool) -> Option<String> {
+    draw_password_screen(
+        "Locked",
+        "PassHub is locked",
+        "Unlock (Enter), log out (ESC)",
+        email,
+        wrong_password.then_some("Wrong master password."),
+        None, // Already locked
+    )
+}
+
+// Asked when the session expired while the vault is open.
+// Returns None if the user pressed ESC to give up or idle_timer expired.
+pub fn draw_session_screen(
+    email: &str,
+    error: Option<&str>,
+    idle_timer: &mut IdleTimer,
+) -> Option<String> {
+    draw_password_screen(
+        "Session expired",
+        "Your session has expired, enter your master password to continue",
+        "Continue (Enter), cancel (ESC)",
+        email,
+        error,
+        Some(idle_timer),
+    )
+}
+
+fn draw_password_screen(
+    title: &str,
+    heading: &str,
+    keys: &str,
+    email: &str,
+    error: Option<&str>,
+    mut idle_timer: Option<&mut IdleTimer>,
+) -> Option<String> {
+    enable_raw_mode().unwrap();
+    let stdout = stdout();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.clear().unwrap();
+
+    let mut password = String::new();
+
+    let result = loop {
+        terminal
+            .draw(|f| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .margin(2)
+                    .constraints(
+                        [
+                            Constraint::Percentage(30),
+                            Constraint::Percentage(20),
+                            Constraint::Percentage(50),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(f.area());
+
+                // Title
+                let header = Paragraph::new(format!("{}\n{}", heading, email))
+                    .style(
+                        Style::default()
+                            .fg(Color::Rgb(255, 163, 26))
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true })
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(title)
+                            .title_bottom(keys),
+                    );
+                f.render_widget(header, chunks[0]);
+
+                // Password Input
+                let password_masked: String = "*".repeat(password.len());
+                let password_paragraph = Paragraph::new(format!("Password: {}", password_masked))
+                    .style(Style::default().fg(Color::White))
+                    .block(Block::default().borders(Borders::ALL).title("Password"));
+                f.render_widget(password_paragraph, chunks[1]);
+
+                if let Some(error) = error {
+                    let error_paragraph = Paragraph::new(error)
+                        .style(Style::default().fg(Color::Red))
+                        .alignment(Alignment::Center)
+                        .wrap(Wrap { trim: true });
+                    f.render_widget(error_paragraph, chunks[2]);
+                }
+            })
+            .unwrap();
+
+        let event = match idle_timer.as_deref_mut().map(IdleTimer::next_event) {
+            Some(Some(event)) => event,
+            Some(None) => {
+                password.zeroize();
+                break None;
+            }
+            None => event::read().unwrap(),
+        };
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                match key.code {
+                    KeyCode::Enter if !password.is_empty() => break Some(password),
+                    KeyCode::Esc => {
+                        password.zeroize();
+                        break None;
+                    }
+                    KeyCode::Backspace => {
+                        password.pop();
+                    }
+                    KeyCode::Char(c) => password.push(c),
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    // Clean up terminal
+    terminal.clear().unwrap();
+    disable_raw_mode().unwrap();
+
+    result
+}
+
+pub fn error_argon2_fail() {
+    // Setup terminal for error screen
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    loop {
+        terminal
+            .draw(|frame| {
+                let size = frame.area();
+                let block = Block::default().borders(Borders::ALL).title("Error");
+                let paragraph = Paragraph::new(
+                    "Argon 2 password hashing failed. You have been logged out. \nPress Enter to continue.",
+                )
+                .block(block);
+                frame.render_widget(paragraph, size);
+            })
+            .unwrap();
+
+        if let Event::Key(key_event) = event::read().unwrap() {
+            if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Enter {
+                break;
+            }
+        }
+    }
+
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+}
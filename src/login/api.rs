@@ -1,31 +1,38 @@
-use reqwest::blocking::Client;
+use super::kdf::KdfParams;
+use crate::client::RsPassClient;
+use crate::error::{PassHubError, Result};
 use serde_json::json;
 
-pub fn login_backend(base_url: &str, email: &str, password_hash: &str) -> Result<String, u16> {
-    let client = Client::new();
-    let url = format!("{}/api/v1/auth/login", base_url); // Combine base URL with API endpoint
+// Key derivation parameters of the account, None if the backend has no prelogin endpoint.
+// Such backends answer 405, a 404 means the backend does not know the account.
+pub fn prelogin(client: &RsPassClient, email: &str) -> Result<Option<KdfParams>> {
+    let payload = json!({ "email": email });
 
+    match client.send(client.post("/api/v1/auth/prelogin").json(&payload)) {
+        Ok(response) => {
+            let params: KdfParams = response.json()?;
+            params.validate()?;
+            Ok(Some(params))
+        }
+        Err(e) if e.status() == Some(405) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn login_backend(client: &RsPassClient, email: &str, password_hash: &str) -> Result<String> {
     let payload = json!({
         "email": email,
         "password_hash": password_hash
     });
 
-    let response = client.post(&url).json(&payload).send();
+    let response = client.send(client.post("/api/v1/auth/login").json(&payload))?;
 
-    match response {
-        Ok(res) => {
-            if res.status().is_success() {
-                // Successful: Extract JSON token
-                let body: serde_json::Value = res.json().unwrap_or_else(|_| json!({}));
-                if let Some(token) = body.get("token").and_then(|t| t.as_str()) {
-                    return Ok(token.to_string());
-                }
-                Err(500) // If no token is present, internal server error
-            } else {
-                // Return the HTTP status code on errors
-                Err(res.status().as_u16())
-            }
-        }
-        Err(_) => Err(500), // Network error or other problem
+    // Extract the JSON token
+    let body: serde_json::Value = response.json()?;
+    match body.get("token").and_then(|t| t.as_str()) {
+        Some(token) => Ok(token.to_string()),
+        None => Err(PassHubError::Serialization(
+            "The backend response contains no token".to_string(),
+        )),
     }
 }
@@ -0,0 +1,198 @@
+use crate::error::{PassHubError, Result};
+use argon2::password_hash::SaltString;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use directories::ProjectDirs;
+use hkdf::Hkdf;
+use log::debug;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+// How the master key of an account is derived, as sent by the prelogin endpoint.
+// Accounts created before have no salt and use the e-mail as salt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub kdf: String,
+    pub memory: u32, // KiB
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub salt: Option<String>, // Random per account, Base64 without padding
+}
+
+const ARGON2ID: &str = "argon2id";
+const SALT_LENGTH: usize = 16;
+
+// Lower bounds, so a backend cannot make the password hash cheap to crack.
+// The upper bound keeps a backend from making the login run out of memory.
+const MIN_MEMORY: u32 = 19456; // 19 MiB
+const MAX_MEMORY: u32 = 1048576; // 1 GiB
+const MIN_ITERATIONS: u32 = 2;
+
+// Separates the password hash sent to the backend from the vault key
+const AUTHENTICATION_INFO: &[u8] = b"passhub authentication hash";
+
+impl KdfParams {
+    // 64 MiB, 3 iterations, 4 lanes with the e-mail as salt
+    pub fn legacy() -> Self {
+        KdfParams {
+            kdf: ARGON2ID.to_string(),
+            memory: 65536,
+            iterations: 3,
+            parallelism: 4,
+            salt: None,
+        }
+    }
+
+    // Same cost as before with a new random salt
+    pub fn random() -> Self {
+        let mut salt = [0u8; SALT_LENGTH];
+        rand::rng().fill(&mut salt);
+        KdfParams {
+            salt: SaltString::encode_b64(&salt)
+                .ok()
+                .map(|salt| salt.as_str().to_string()),
+            ..KdfParams::legacy()
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.salt.is_none()
+    }
+
+    // Refuse parameters PassHub cannot use or that are too weak
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| {
+            Err(PassHubError::Serialization(format!(
+                "The backend sent unusable key derivation parameters: {}",
+                reason
+            )))
+        };
+        if self.kdf != ARGON2ID {
+            return invalid(&format!("{} is not supported", self.kdf));
+        }
+        if !(MIN_MEMORY..=MAX_MEMORY).contains(&self.memory) {
+            return invalid(&format!("memory of {} KiB", self.memory));
+        }
+        if self.iterations < MIN_ITERATIONS || self.parallelism == 0 {
+            return invalid("too few iterations or lanes");
+        }
+        match &self.salt {
+            Some(salt) if SaltString::from_b64(salt).is_err() => invalid("the salt is not Base64"),
+            Some(salt) if salt.len() < SALT_LENGTH * 4 / 3 => invalid("the salt is too short"),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Password hash sent to the backend, expanded from the raw Argon2 output of the master key
+pub fn authentication_hash(master_key: &str) -> Option<String> {
+    let master_key = argon2::PasswordHash::new(master_key).ok()?;
+    let mut hash = [0u8; 32];
+    Hkdf::<Sha256>::new(None, master_key.hash?.as_bytes())
+        .expand(AUTHENTICATION_INFO, &mut hash)
+        .ok()?;
+    Some(STANDARD.encode(hash))
+}
+
+// The parameters are cached per e-mail, so the vault can be unlocked offline
+fn kdf_file() -> Option<PathBuf> {
+    ProjectDirs::from("dev", "passhub", "passhub")
+        .map(|proj_dirs| proj_dirs.config_dir().join("kdf.json"))
+}
+
+fn load_all() -> HashMap<String, KdfParams> {
+    kdf_file()
+        .and_then(|kdf_file| fs::read_to_string(kdf_file).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Parameters of the last login with this e-mail, legacy if there was none
+pub fn load(email: &str) -> KdfParams {
+    load_all().remove(email).unwrap_or_else(KdfParams::legacy)
+}
+
+pub fn save(email: &str, params: &KdfParams) {
+    let kdf_file = match kdf_file() {
+        Some(kdf_file) => kdf_file,
+        None => return,
+    };
+    let mut all = load_all();
+    all.insert(email.to_string(), params.clone());
+
+    let result = fs::create_dir_all(kdf_file.parent().unwrap())
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_string_pretty(&all).map_err(|e| e.to_string()))
+        .and_then(|json| fs::write(&kdf_file, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        debug!("Login: Error writing kdf.json: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_accepts_the_minimum_and_legacy_parameters() {
+        let minimum = KdfParams {
+            memory: MIN_MEMORY,
+            iterations: MIN_ITERATIONS,
+            ..KdfParams::random()
+        };
+        assert!(minimum.validate().is_ok());
+        assert!(KdfParams::legacy().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_refuses_weak_or_unusable_parameters() {
+        let random = KdfParams::random();
+        let refused = [
+            KdfParams {
+                memory: MIN_MEMORY - 1,
+                ..random.clone()
+            },
+            KdfParams {
+                memory: MAX_MEMORY + 1,
+                ..random.clone()
+            },
+            KdfParams {
+                iterations: MIN_ITERATIONS - 1,
+                ..random.clone()
+            },
+            KdfParams {
+                parallelism: 0,
+                ..random.clone()
+            },
+            KdfParams {
+                kdf: "pbkdf2".to_string(),
+                ..random.clone()
+            },
+            KdfParams {
+                salt: Some("c2FsdA".to_string()),
+                ..random.clone()
+            },
+            KdfParams {
+                salt: Some("not base64!".to_string()),
+                ..random
+            },
+        ];
+        for params in refused {
+            assert!(params.validate().is_err(), "{:?}", params);
+        }
+    }
+
+    #[test]
+    fn prelogin_response_without_salt_is_legacy() {
+        let params: KdfParams = serde_json::from_str(
+            r#"{ "kdf": "argon2id", "memory": 65536, "iterations": 3, "parallelism": 4, "salt": null }"#,
+        )
+        .unwrap();
+        assert_eq!(params, KdfParams::legacy());
+        assert!(params.is_legacy());
+    }
+}
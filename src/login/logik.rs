@@ -1,13 +1,20 @@
-use super::{api, view};
+use super::{api, kdf, view};
+use crate::client::RsPassClient;
+use crate::error::PassHubError;
+use crate::menue;
+use crate::password_change::logik as password_change;
 use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Algorithm, Argon2, Params, Version,
 };
 use directories::ProjectDirs;
+use log::debug;
+use serde_json::Value;
 use std::{fs, io::Read, io::Write};
 use zeroize::Zeroize;
 
-pub fn login(backend_url: &str) -> (String, String) {
+// Returns the logged in client and the master key
+pub fn login(client: &RsPassClient) -> (RsPassClient, String) {
     loop {
         // Load email from storage
         let stored_email = get_mail_from_storage();
@@ -15,40 +22,57 @@ pub fn login(backend_url: &str) -> (String, String) {
         // Prompt for username and password
         let (email, mut cleartext_password) = view::draw_login_screen(stored_email);
 
-        // Hash the password
-        match hash_argon_2_master_key(&cleartext_password, &email) {
-            Ok(mut master_key) => {
-                let master_password_hash =
-                    hash_argon_2_master_password_hash(&master_key, &cleartext_password);
-                cleartext_password.zeroize(); // Clear plaintext password from memory
-
-                // Proceed with backend login
-                match api::login_backend(
-                    backend_url,
-                    &email,
-                    &master_password_hash
-                        .expect("Login: Error occurred extracting master password"),
-                ) {
-                    Ok(token) => {
-                        save_email_to_storage(&email); // Save email
-                        return (token, master_key); // Return JWT token
-                    }
-                    Err(status) => {
-                        match status {
-                            400 => view::error_bad_request(),  // Bad request
-                            401 => view::error_unauthorized(), // Unauthorized
-                            404 => {
-                                master_key.zeroize();
-                                view::error_user_not_found();
-                            } // Not found
-                            500 => view::error_network(),      // Internal server error
-                            _ => view::error_unknown(),        // Unknown error
-                        }
-                    }
+        let result = login_with_credentials(client, &email, &cleartext_password);
+        cleartext_password.zeroize(); // Clear plaintext password from memory
+
+        match result {
+            Ok((session, master_key)) => {
+                save_email_to_storage(&email); // Save email
+                return (session, master_key); // Client with the JWT token
+            }
+            Err(LoginError::Argon2) => {
+                view::error_argon2_fail();
+                std::process::exit(1);
+            }
+            Err(LoginError::Backend(e)) => {
+                match e.status() {
+                    Some(400) => view::error_bad_request(),    // Bad request
+                    Some(401) => view::error_unauthorized(),   // Unauthorized
+                    Some(404) => view::error_user_not_found(), // Not found
+                    _ => view::error(&e.to_string()),
                 }
             }
+        }
+    }
+}
+
+// Unlock the cached vault without the backend, returns (master key, decrypted vault)
+pub fn login_offline(encrypted_data: &str) -> (String, Value) {
+    loop {
+        let stored_email = get_mail_from_storage();
+        let (email, mut cleartext_password) = view::draw_login_screen(stored_email);
+
+        let master_key = hash_argon_2_master_key(&cleartext_password, &email, &kdf::load(&email));
+        cleartext_password.zeroize(); // Clear plaintext password from memory
+
+        match master_key {
+            Ok(mut master_key) => match menue::api::decrypt_vault(encrypted_data, &master_key) {
+                Ok(Some(json_data)) => return (master_key, json_data),
+                // AES-GCM authentication fails for a wrong password or e-mail
+                Err(PassHubError::Decryption) => {
+                    master_key.zeroize();
+                    view::error_unauthorized();
+                }
+                Ok(None) => {
+                    master_key.zeroize();
+                    view::error("The cached vault is empty.");
+                }
+                Err(e) => {
+                    master_key.zeroize();
+                    view::error(&e.to_string());
+                }
+            },
             Err(_e) => {
-                cleartext_password.zeroize();
                 view::error_argon2_fail();
                 std::process::exit(1);
             }
@@ -56,12 +80,87 @@ pub fn login(backend_url: &str) -> (String, String) {
     }
 }
 
-fn hash_argon_2_master_key(
+#[derive(Debug)]
+pub enum LoginError {
+    Argon2,
+    Backend(PassHubError),
+}
+
+// Derive the keys and log in without any user interaction, returns (session, master key).
+// Accounts still salted with the e-mail are moved to a random salt if the backend supports it.
+pub fn login_with_credentials(
+    client: &RsPassClient,
+    email: &str,
+    cleartext_password: &str,
+) -> Result<(RsPassClient, String), LoginError> {
+    let prelogin = api::prelogin(client, email).map_err(LoginError::Backend)?;
+    let params = prelogin.clone().unwrap_or_else(kdf::KdfParams::legacy);
+    let (mut master_key, master_password_hash) =
+        derive_keys(email, cleartext_password, &params).map_err(|_| LoginError::Argon2)?;
+
+    // Proceed with backend login
+    let session = match api::login_backend(client, email, &master_password_hash) {
+        Ok(token) => client.with_token(&token),
+        Err(e) => {
+            master_key.zeroize();
+            return Err(LoginError::Backend(e));
+        }
+    };
+    kdf::save(email, &params);
+
+    // The backend knows per-account salts, but this account has none yet
+    if prelogin.is_some_and(|params| params.is_legacy()) {
+        let new_params = kdf::KdfParams::random();
+        match password_change::rekey(
+            &session,
+            email,
+            &master_key,
+            cleartext_password,
+            &new_params,
+        ) {
+            Ok(new_key) => {
+                master_key.zeroize();
+                master_key = new_key;
+                kdf::save(email, &new_params);
+            }
+            // The old keys keep working, the migration is tried again on the next login
+            Err(e) => debug!("Login: Error moving the account to a random salt: {:?}", e),
+        }
+    }
+    Ok((session, master_key))
+}
+
+// Derive (master key, master password hash) from the cleartext password
+pub fn derive_keys(
+    email: &str,
+    cleartext_password: &str,
+    params: &kdf::KdfParams,
+) -> Result<(String, String), argon2::password_hash::Error> {
+    let mut master_key = hash_argon_2_master_key(cleartext_password, email, params)?;
+    let master_password_hash = if params.is_legacy() {
+        hash_argon_2_master_password_hash(&master_key, cleartext_password)
+    } else {
+        kdf::authentication_hash(&master_key).ok_or(argon2::password_hash::Error::Crypto)
+    };
+    match master_password_hash {
+        Ok(master_password_hash) => Ok((master_key, master_password_hash)),
+        Err(e) => {
+            master_key.zeroize();
+            Err(e)
+        }
+    }
+}
+
+pub fn hash_argon_2_master_key(
     password: &str,
     email: &str,
+    params: &kdf::KdfParams,
 ) -> Result<String, argon2::password_hash::Error> {
-    let salt = SaltString::encode_b64(email.as_bytes())?;
-    let params = Params::new(65536, 3, 4, None)?; // 64 MiB, 3 iterations, 4 lane/thread
+    let salt = match &params.salt {
+        Some(salt) => SaltString::from_b64(salt)?,
+        None => SaltString::encode_b64(email.as_bytes())?, // Accounts from before per-account salts
+    };
+    let params = Params::new(params.memory, params.iterations, params.parallelism, None)?;
     let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
 
     // Hash the password
@@ -76,6 +175,7 @@ fn hash_argon_2_master_key(
     Ok(password_hash)
 }
 
+// Password hash of accounts from before per-account salts, salted with the password itself
 fn hash_argon_2_master_password_hash(
     master_key: &str,
     master_password: &str,
@@ -92,7 +192,7 @@ fn hash_argon_2_master_password_hash(
     Ok(password_hash)
 }
 
-fn get_mail_from_storage() -> String {
+pub fn get_mail_from_storage() -> String {
     // Get the project directory
     if let Some(proj_dirs) = ProjectDirs::from("dev", "passhub", "passhub") {
         let config_dir = proj_dirs.config_dir();
@@ -120,7 +220,7 @@ fn get_mail_from_storage() -> String {
     }
 }
 
-fn save_email_to_storage(email: &str) {
+pub fn save_email_to_storage(email: &str) {
     if let Some(proj_dirs) = ProjectDirs::from("dev", "passhub", "passhub") {
         let config_dir = proj_dirs.config_dir();
         let config_file = config_dir.join("mail.txt");
@@ -131,3 +231,126 @@ fn save_email_to_storage(email: &str) {
             .expect("Error writing to the file");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::login::kdf::KdfParams;
+    use crate::menue::api as vault_api;
+    use crate::testing::{self, isolated, PASSWORD};
+    use serde_json::json;
+    use std::path::Path;
+
+    fn mock(state: Option<&Path>, prelogin: bool) -> RsPassClient {
+        testing::mock(state, prelogin).0
+    }
+
+    // Register like the register screen does and store a vault with one entry
+    fn register(client: &RsPassClient, email: &str, params: &KdfParams) {
+        let (session, master_key) = testing::register(client, email, params);
+        let vault = json!([{ "name": "example" }]);
+        vault_api::update(&session, &master_key, &vault, None).unwrap();
+    }
+
+    fn vault_name(session: &RsPassClient, master_key: &str) -> String {
+        let (vault, _) = vault_api::fetch(session, master_key).unwrap();
+        vault.unwrap()[0]["name"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn new_account_logs_in_with_its_random_salt() {
+        let _home = isolated();
+        let client = mock(None, true);
+        let email = "new-account@example.com";
+        let params = KdfParams::random();
+        register(&client, email, &params);
+
+        let (session, master_key) = login_with_credentials(&client, email, PASSWORD).unwrap();
+        assert_eq!(kdf::load(email), params);
+        assert_eq!(vault_name(&session, &master_key), "example");
+
+        let wrong = login_with_credentials(&client, email, "Wrong-Horse-9");
+        assert!(matches!(wrong, Err(LoginError::Backend(e)) if e.status() == Some(401)));
+    }
+
+    #[test]
+    fn legacy_account_without_prelogin() {
+        let _home = isolated();
+        let client = mock(None, false);
+        let email = "legacy-account@example.com";
+        register(&client, email, &KdfParams::legacy());
+
+        let (session, master_key) = login_with_credentials(&client, email, PASSWORD).unwrap();
+        assert!(kdf::load(email).is_legacy());
+        let legacy_key = hash_argon_2_master_key(PASSWORD, email, &KdfParams::legacy());
+        assert_eq!(master_key, legacy_key.unwrap());
+        assert_eq!(vault_name(&session, &master_key), "example");
+    }
+
+    #[test]
+    fn legacy_account_moves_to_a_random_salt() {
+        let _home = isolated();
+        let state = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(state.path(), "{}").unwrap();
+        let email = "migrated-account@example.com";
+        register(
+            &mock(Some(state.path()), false),
+            email,
+            &KdfParams::legacy(),
+        );
+
+        // The same accounts on a backend that knows per-account salts
+        let client = mock(Some(state.path()), true);
+        let (session, master_key) = login_with_credentials(&client, email, PASSWORD).unwrap();
+        let params = kdf::load(email);
+        assert!(!params.is_legacy());
+        assert_eq!(vault_name(&session, &master_key), "example");
+
+        // The next login derives the same key from the new salt
+        let (_, again) = login_with_credentials(&client, email, PASSWORD).unwrap();
+        assert_eq!(again, master_key);
+        assert_eq!(kdf::load(email), params);
+    }
+
+    // Client for a backend that answers every request with status
+    fn answering(status: u16) -> RsPassClient {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let _ = request.respond(tiny_http::Response::empty(status));
+            }
+        });
+        RsPassClient::with_settings(&url, &Default::default()).unwrap()
+    }
+
+    #[test]
+    fn prelogin_falls_back_only_without_the_endpoint() {
+        let email = "unknown-account@example.com";
+        assert_eq!(api::prelogin(&answering(405), email).unwrap(), None);
+
+        // Not the missing endpoint but an unknown account, it must not get the e-mail salt
+        let unknown = api::prelogin(&answering(404), email);
+        assert_eq!(unknown.unwrap_err().status(), Some(404));
+        let login = login_with_credentials(&answering(404), email, PASSWORD);
+        assert!(matches!(login, Err(LoginError::Backend(e)) if e.status() == Some(404)));
+    }
+
+    #[test]
+    fn prelogin_refuses_weak_parameters() {
+        let _home = isolated();
+        let client = mock(None, true);
+        let email = "weak-account@example.com";
+        let weak = KdfParams {
+            memory: 8192,
+            ..KdfParams::random()
+        };
+        register(&client, email, &weak);
+
+        let result = login_with_credentials(&client, email, PASSWORD);
+        assert!(matches!(
+            result,
+            Err(LoginError::Backend(PassHubError::Serialization(_)))
+        ));
+    }
+}
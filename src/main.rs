@@ -1,5 +1,10 @@
+use clap::Parser;
 use zeroize::Zeroize;
 
+mod client;
+mod error;
+#[cfg(test)]
+mod testing;
 mod view;
 mod url_check {
     pub mod api;
@@ -8,6 +13,7 @@ mod url_check {
 }
 mod login {
     pub mod api;
+    pub mod kdf;
     pub mod logik;
     pub mod view;
 }
@@ -16,8 +22,15 @@ mod register {
     pub mod logik;
     pub mod view;
 }
+mod lock {
+    pub mod logik;
+    pub mod view;
+}
 mod menue {
     pub mod api;
+    pub mod cache;
+    pub mod clipboard;
+    pub mod envelope;
     pub mod logik;
     pub mod view;
 }
@@ -26,34 +39,88 @@ mod delete {
     pub mod logik;
     pub mod view;
 }
+mod sync {
+    pub mod logik;
+    pub mod view;
+}
+mod session {
+    pub mod logik;
+}
+mod settings {
+    pub mod logik;
+}
+mod totp {
+    pub mod logik;
+}
+mod breach {
+    pub mod api;
+    pub mod logik;
+}
+mod health {
+    pub mod logik;
+    pub mod view;
+}
+mod generator {
+    pub mod logik;
+    pub mod view;
+}
+mod password_change {
+    pub mod api;
+    pub mod logik;
+    pub mod view;
+}
+mod cli {
+    pub mod logik;
+    pub mod view;
+}
+mod export {
+    pub mod logik;
+    pub mod view;
+}
+mod import {
+    pub mod logik;
+    pub mod view;
+}
 
 //Main function
 fn main() {
+    //Run a non-interactive command if one was given
+    let mut cli = cli::logik::Cli::parse();
+    if let Some(command) = cli.command.take() {
+        std::process::exit(cli::logik::run(cli, command, &mut std::io::stdout()));
+    }
+
     //Display welcome screen
     let mut first_time = view::draw_welcome_screen();
 
     //Loop - Query JWT token and password hash, then display the menu
     loop {
-        //Get backend URL
-        let backend_url: String = url_check::logik::get_backend_url();
-        let token: String;
+        //Connect to the backend, or browse the cached vault if it is unreachable
+        let client = match url_check::logik::get_client() {
+            Some(client) => client,
+            None => {
+                menue::logik::offline_menue();
+                continue;
+            }
+        };
+        let session: client::RsPassClient;
         let mut master_key: String;
 
         match first_time {
             Some('r') => {
-                (token, master_key) = register::logik::register(&backend_url);
+                (session, master_key) = register::logik::register(&client);
                 first_time = Some('l'); // Set screen to login after next logout
             }
             Some('d') => {
-                (token, master_key) = login::logik::login(&backend_url);
-                delete::logik::delete(&backend_url, &token);
+                (session, master_key) = login::logik::login(&client);
+                delete::logik::delete(&session);
                 master_key.zeroize();
                 std::process::exit(0);
             }
             _ => {
-                (token, master_key) = login::logik::login(&backend_url);
+                (session, master_key) = login::logik::login(&client);
             }
         }
-        menue::logik::main_menue(&backend_url, &token, master_key);
+        menue::logik::main_menue(&session, master_key);
     }
 }
@@ -1,158 +1,131 @@
-use aes_gcm::aead::{Aead, KeyInit};
-use aes_gcm::{Aes256Gcm, Key, Nonce};
+use super::envelope;
+use crate::client::RsPassClient;
+use crate::error::{PassHubError, Result};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
-use reqwest::blocking::Client;
+use reqwest::blocking::Response;
+use reqwest::header::{ETAG, IF_MATCH};
 use serde_json::Value;
-use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
 
-pub fn fetch(
-    backend_url: &String,
-    jwt_token: &String,
-    master_key: &str,
-) -> Result<(u16, Option<Value>), Box<dyn std::error::Error>> {
-    // Create HTTP client
-    let client = Client::new();
-    let request_url = format!("{}/api/v1/sync/fetch", backend_url);
+// Revision the backend stored the vault under, None if it does not track revisions
+pub type Revision = Option<String>;
 
-    // Fetch API data
-    let response = client
-        .get(&request_url)
-        .header("Authorization", format!("Bearer {}", jwt_token))
-        .send()?;
-
-    let status_code = response.status().as_u16();
-
-    match status_code {
-        200 => {
-            // Check if a JSON response is present
-            let json_response: Option<Value> = response.json().ok();
-
-            // If the JSON response is empty, return only the status code
-            if json_response.is_none() || json_response == Some(Value::Null) {
-                return Ok((status_code, None));
-            }
-
-            // Extract Base64 string from the JSON response
-            let json_response = json_response.expect("Menue: Error extracting JSON response");
-            let base64_data = json_response["encrypted_data"].as_str().unwrap_or("");
-
-            // Base64 decoding
-            let decoded_data = STANDARD.decode(base64_data)?;
-
-            // Check if minimum length is met for AES-GCM decryption
-            if decoded_data.len() < 12 {
-                return Ok((status_code, None));
-            }
-
-            // Convert user key to bytes
-            let key = derive_key_from_hash(master_key);
-            let nonce = &decoded_data[..12]; // Use the first 12 bytes as nonce
-            let ciphertext = &decoded_data[12..]; // Rest as ciphertext
-
-            // Decrypt data
-            let decrypted_data = decrypt_aes256_gcm(&key, nonce, ciphertext);
-
-            // Convert to JSON
-            let json_data: Value = serde_json::from_slice(&decrypted_data)?;
-
-            Ok((status_code, Some(json_data)))
-        }
-        401 | 500 => Ok((status_code, None)),
-        _ => Ok((status_code, None)),
-    }
+// The vault as stored on the backend
+pub struct RemoteVault {
+    pub encrypted_data: String, // Base64 encoded blob
+    pub revision: Revision,
 }
 
-fn decrypt_aes256_gcm(key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Vec<u8> {
-    // Initialize AES-GCM
-    let cipher = Aes256Gcm::new(Key::<aes_gcm::aes::Aes256>::from_slice(key));
-    let nonce = Nonce::from_slice(nonce);
-
-    // Decrypt
-    cipher
-        .decrypt(nonce, ciphertext)
-        .expect("Menue: Error decrypting data")
+// Fetch and decrypt the vault, returns the vault and its revision
+pub fn fetch(client: &RsPassClient, master_key: &str) -> Result<(Option<Value>, Revision)> {
+    match fetch_encrypted(client)? {
+        Some(remote) => Ok((
+            decrypt_vault(&remote.encrypted_data, master_key)?,
+            remote.revision,
+        )),
+        None => Ok((None, None)),
+    }
 }
 
-fn derive_key_from_hash(password_hash: &str) -> [u8; 32] {
-    // Compute SHA256 from the hash
-    let mut hasher = Sha256::default();
-    hasher.update(password_hash.as_bytes());
-    let result = hasher.finalize();
-
-    // Return 32-byte key
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&result[..32]);
-    key
+// Fetch the vault without decrypting it, None if nothing is stored yet
+pub fn fetch_encrypted(client: &RsPassClient) -> Result<Option<RemoteVault>> {
+    // Fetch API data
+    let response = client.send(client.get("/api/v1/sync/fetch"))?;
+    let etag = etag(&response);
+
+    // An empty response means no vault has been stored yet
+    let json_response = match response.json::<Value>() {
+        Ok(Value::Null) | Err(_) => return Ok(None),
+        Ok(json_response) => json_response,
+    };
+
+    // Extract Base64 string from the JSON response
+    let base64_data = json_response["encrypted_data"].as_str().unwrap_or("");
+
+    Ok(Some(RemoteVault {
+        encrypted_data: base64_data.to_string(),
+        revision: etag.or_else(|| revision_of(&json_response)),
+    }))
 }
 
-pub fn update(
-    backend_url: &String,
-    jwt_token: &String,
-    master_key: &str,
-    json_data: &Value,
-) -> Result<u16, Box<dyn std::error::Error>> {
-    // Create HTTP client
-    let client = Client::new();
-    let request_url = format!("{}/api/v1/sync/update", backend_url);
+// Decrypt a Base64 encoded vault blob, None if the blob holds no data
+pub fn decrypt_vault(base64_data: &str, master_key: &str) -> Result<Option<Value>> {
+    // Base64 decoding
+    let decoded_data = STANDARD
+        .decode(base64_data)
+        .map_err(|e| PassHubError::CorruptVault(e.to_string()))?;
 
-    // Convert user key to bytes
-    let key = derive_key_from_hash(master_key);
-
-    // Initialize encryption
-    let cipher = Aes256Gcm::new(Key::<aes_gcm::aes::Aes256>::from_slice(&key));
+    // Check if minimum length is met for AES-GCM decryption
+    if decoded_data.len() < 12 {
+        return Ok(None);
+    }
 
-    // Generate nonce (12 random bytes)
-    let nonce = generate_random_nonce();
+    // Decrypt data, fails if the key is wrong or the data was tampered with
+    let decrypted_data = envelope::open(&decoded_data, master_key)?;
 
-    // Serialize JSON data to a string
-    let json_string = serde_json::to_string(&json_data)?;
+    // Convert to JSON
+    let json_data: Value = serde_json::from_slice(&decrypted_data)
+        .map_err(|e| PassHubError::CorruptVault(e.to_string()))?;
 
-    // Encrypt JSON data
-    let ciphertext = cipher
-        .encrypt(Nonce::from_slice(&nonce), json_string.as_bytes())
-        .map_err(|e| format!("Encryption error: {:?}", e))?;
-
-    // Combine nonce and ciphertext
-    let mut encrypted_data = nonce.to_vec();
-    encrypted_data.extend(ciphertext);
+    Ok(Some(json_data))
+}
 
+// Upload the vault, returns the new revision.
+// With a revision the backend rejects the update with 409 or 412 if the vault changed since.
+pub fn update(
+    client: &RsPassClient,
+    master_key: &str,
+    json_data: &Value,
+    revision: Option<&str>,
+) -> Result<Revision> {
     // Create Base64-encoded data
-    let base64_data = STANDARD.encode(&encrypted_data);
+    let base64_data = encrypt_vault(json_data, master_key)?;
 
     // Embed Base64 data in JSON structure
-    let json_request = serde_json::json!({ "encrypted_data": base64_data });
+    let mut json_request = serde_json::json!({ "encrypted_data": base64_data });
+    let mut request = client.post("/api/v1/sync/update");
+    if let Some(revision) = revision {
+        json_request["revision"] = Value::String(revision.to_string());
+        request = request.header(IF_MATCH, format!("\"{}\"", revision));
+    }
 
     // Send request
-    let response = client
-        .post(&request_url)
-        .header("Authorization", format!("Bearer {}", jwt_token))
-        .json(&json_request)
-        .send()?;
-
-    // Return HTTP status code
-    Ok(response.status().as_u16())
-}
+    let response = client.send(request.json(&json_request))?;
 
-fn generate_random_nonce() -> [u8; 12] {
-    use rand::Rng;
+    // Return the revision the vault is stored under now
+    let etag = etag(&response);
+    let body: Option<Value> = response.json().ok();
+    Ok(etag.or_else(|| body.as_ref().and_then(revision_of)))
+}
 
-    let mut nonce = [0u8; 12];
-    rand::thread_rng().fill(&mut nonce);
-    nonce
+// Revision from the ETag header, without quotes
+fn etag(response: &Response) -> Option<String> {
+    let etag = response.headers().get(ETAG)?.to_str().ok()?;
+    Some(etag.trim_start_matches("W/").trim_matches('"').to_string())
 }
 
-pub fn logout(backend_url: &String, jwt_token: &String) -> Result<u16, Box<dyn std::error::Error>> {
-    let client = Client::new();
+// Revision from the response body, backends send a number or a string
+fn revision_of(body: &Value) -> Option<String> {
+    match &body["revision"] {
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(string) => Some(string.clone()),
+        _ => None,
+    }
+}
 
-    let request_url = format!("{}/api/v1/account/logout", backend_url);
+// Encrypt the vault under the master key, always in the current envelope format
+pub fn encrypt_vault(json_data: &Value, master_key: &str) -> Result<String> {
+    // Serialize JSON data to a string
+    let mut json_string = serde_json::to_string(&json_data)?;
 
-    let response = client
-        .get(&request_url)
-        .header("Authorization", format!("Bearer {}", jwt_token))
-        .send()?;
+    let encrypted_data = envelope::seal(json_string.as_bytes(), master_key);
+    json_string.zeroize();
 
-    let status_code = response.status().as_u16();
+    Ok(STANDARD.encode(encrypted_data?))
+}
 
-    Ok(status_code)
+pub fn logout(client: &RsPassClient) -> Result<()> {
+    client.send(client.get("/api/v1/account/logout"))?;
+    Ok(())
 }
@@ -0,0 +1,64 @@
+use directories::ProjectDirs;
+use log::debug;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// The cache holds the vault exactly as fetched, so it stays encrypted under the master key
+
+fn cache_file() -> Option<PathBuf> {
+    ProjectDirs::from("dev", "passhub", "passhub")
+        .map(|proj_dirs| proj_dirs.data_dir().join("vault.cache"))
+}
+
+pub fn save(encrypted_data: &str) {
+    if let Some(proj_dirs) = ProjectDirs::from("dev", "passhub", "passhub") {
+        let data_dir = proj_dirs.data_dir();
+        if fs::create_dir_all(data_dir).is_err() {
+            debug!("Cache: Error creating the data directory");
+            return;
+        }
+        write_cache_file(&data_dir.join("vault.cache"), encrypted_data);
+    }
+}
+
+fn write_cache_file(cache_file: &Path, encrypted_data: &str) {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600); // Only readable by the current user
+    }
+
+    match options.open(cache_file) {
+        Ok(mut file) => {
+            if file.write_all(encrypted_data.as_bytes()).is_err() {
+                debug!("Cache: Error writing the vault cache");
+            }
+        }
+        Err(_) => debug!("Cache: Error creating the vault cache"),
+    }
+}
+
+pub fn load() -> Option<String> {
+    let encrypted_data = fs::read_to_string(cache_file()?).ok()?;
+    let encrypted_data = encrypted_data.trim();
+    if encrypted_data.is_empty() {
+        None
+    } else {
+        Some(encrypted_data.to_string())
+    }
+}
+
+pub fn exists() -> bool {
+    load().is_some()
+}
+
+pub fn clear() {
+    if let Some(cache_file) = cache_file() {
+        if cache_file.exists() && fs::remove_file(&cache_file).is_err() {
+            debug!("Cache: Error removing the vault cache");
+        }
+    }
+}
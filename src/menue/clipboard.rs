@@ -0,0 +1,104 @@
+use crate::settings;
+use copypasta::{ClipboardContext, ClipboardProvider};
+use log::debug;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Copied secrets are removed again after the configured timeout. Only a hash of the
+// copied value is kept, so the clipboard is only cleared if it still holds our value.
+
+struct PendingClear {
+    generation: u64,
+    value_hash: [u8; 32],
+    deadline: Instant,
+}
+
+static PENDING: Mutex<Option<PendingClear>> = Mutex::new(None);
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static COPIES: AtomicU64 = AtomicU64::new(0);
+
+pub fn copy(content: String) {
+    let value_hash = hash(&content);
+    match ClipboardContext::new().and_then(|mut clipboard| clipboard.set_contents(content)) {
+        Ok(()) => {}
+        Err(e) => {
+            debug!("Clipboard: Error setting the clipboard: {}", e);
+            return;
+        }
+    }
+    COPIES.fetch_add(1, Ordering::SeqCst);
+
+    let timeout = settings::logik::load().clipboard_timeout_seconds;
+    if timeout == 0 {
+        *PENDING.lock().unwrap() = None;
+        return;
+    }
+
+    // A newer copy replaces the pending clear of an older one
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    *PENDING.lock().unwrap() = Some(PendingClear {
+        generation,
+        value_hash,
+        deadline: Instant::now() + Duration::from_secs(timeout),
+    });
+
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(timeout));
+        let mut pending = PENDING.lock().unwrap();
+        if pending.as_ref().map(|p| p.generation) == Some(generation) {
+            if let Some(p) = pending.take() {
+                clear_if_unchanged(&p.value_hash);
+            }
+        }
+    });
+}
+
+// Number of successful copies, used to tell whether an entry was used
+pub fn copy_count() -> u64 {
+    COPIES.load(Ordering::SeqCst)
+}
+
+pub fn paste() -> Option<String> {
+    let mut clipboard = ClipboardContext::new().ok()?;
+    clipboard.get_contents().ok()
+}
+
+// Seconds until the clipboard is cleared, None if nothing is pending
+pub fn remaining_seconds() -> Option<u64> {
+    let pending = PENDING.lock().unwrap();
+    pending.as_ref().map(|p| {
+        let remaining = p.deadline.saturating_duration_since(Instant::now());
+        // Round up, so the countdown does not show 0 while the value is still there
+        remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
+    })
+}
+
+// Clear right away, e.g. on logout
+pub fn clear_now() {
+    if let Some(p) = PENDING.lock().unwrap().take() {
+        clear_if_unchanged(&p.value_hash);
+    }
+}
+
+fn clear_if_unchanged(value_hash: &[u8; 32]) {
+    let mut clipboard = match ClipboardContext::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            debug!("Clipboard: Error opening the clipboard: {}", e);
+            return;
+        }
+    };
+    // Leave the clipboard alone if the user copied something else in the meantime
+    let unchanged =
+        matches!(clipboard.get_contents(), Ok(content) if hash(&content) == *value_hash);
+    if unchanged && clipboard.set_contents(String::new()).is_err() {
+        debug!("Clipboard: Error clearing the clipboard");
+    }
+}
+
+fn hash(content: &str) -> [u8; 32] {
+    Sha256::digest(content.as_bytes()).into()
+}
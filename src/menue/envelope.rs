@@ -0,0 +1,265 @@
+use crate::error::{PassHubError, Result};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::PasswordHash;
+use hkdf::Hkdf;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+// Layout of an encrypted vault, all numbers little endian:
+// magic (4) | version (1) | KDF id (1) | Argon2 m, t, p (3 x 4) | HKDF salt (16) | nonce (12) | ciphertext
+// The header is authenticated as associated data, so it cannot be changed unnoticed.
+// Vaults written before had no header, only nonce (12) | ciphertext. They are still
+// read and are stored in the current format with the next save.
+
+const MAGIC: &[u8; 4] = b"PHVT";
+const VERSION: u8 = 1;
+// Argon2id master key, AES key expanded from its raw output with HKDF-SHA256
+const KDF_ARGON2ID_HKDF_SHA256: u8 = 1;
+// Separates the vault key from the password hash sent to the backend
+const HKDF_INFO: &[u8] = b"passhub vault encryption key";
+
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const HEADER_LENGTH: usize = 4 + 1 + 1 + 3 * 4 + SALT_LENGTH;
+
+// Encrypt the serialized vault in the current format
+pub fn seal(plaintext: &[u8], master_key: &str) -> Result<Vec<u8>> {
+    let master_key = PasswordHash::new(master_key).map_err(|e| {
+        PassHubError::Serialization(format!("The master key is not an Argon2 hash: {}", e))
+    })?;
+    let params = argon2_params(&master_key).ok_or_else(|| {
+        PassHubError::Serialization("The master key has no Argon2 parameters".to_string())
+    })?;
+
+    let mut salt = [0u8; SALT_LENGTH];
+    rand::rng().fill(&mut salt);
+    let mut nonce = [0u8; NONCE_LENGTH];
+    rand::rng().fill(&mut nonce);
+
+    let mut data = Vec::with_capacity(HEADER_LENGTH + NONCE_LENGTH + plaintext.len() + 16);
+    data.extend_from_slice(MAGIC);
+    data.push(VERSION);
+    data.push(KDF_ARGON2ID_HKDF_SHA256);
+    for param in params {
+        data.extend_from_slice(&param.to_le_bytes());
+    }
+    data.extend_from_slice(&salt);
+
+    let mut key = vault_key(&master_key, &salt).ok_or_else(|| {
+        PassHubError::Serialization("The master key holds no Argon2 output".to_string())
+    })?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    key.zeroize();
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &data,
+            },
+        )
+        .map_err(|e| PassHubError::Serialization(format!("Encryption error: {:?}", e)))?;
+
+    data.extend_from_slice(&nonce);
+    data.extend(ciphertext);
+    Ok(data)
+}
+
+// Decrypt a vault in the current or the legacy format
+pub fn open(data: &[u8], master_key: &str) -> Result<Vec<u8>> {
+    if !data.starts_with(MAGIC) {
+        return open_legacy(data, master_key);
+    }
+    // A legacy nonce can start with the magic by chance
+    open_versioned(data, master_key).or_else(|e| match e {
+        PassHubError::Decryption => open_legacy(data, master_key).map_err(|_| e),
+        e => Err(e),
+    })
+}
+
+fn open_versioned(data: &[u8], master_key: &str) -> Result<Vec<u8>> {
+    if data.len() < HEADER_LENGTH + NONCE_LENGTH {
+        return Err(PassHubError::CorruptVault(
+            "The vault header is incomplete".to_string(),
+        ));
+    }
+    let (header, rest) = data.split_at(HEADER_LENGTH);
+    if header[4] != VERSION {
+        return Err(PassHubError::CorruptVault(format!(
+            "Vault format version {} is not supported, please update PassHub",
+            header[4]
+        )));
+    }
+    if header[5] != KDF_ARGON2ID_HKDF_SHA256 {
+        return Err(PassHubError::CorruptVault(format!(
+            "Key derivation {} is not supported",
+            header[5]
+        )));
+    }
+
+    // A master key derived with other parameters cannot be the right one
+    let master_key = PasswordHash::new(master_key).map_err(|_| PassHubError::Decryption)?;
+    let param = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+    if argon2_params(&master_key) != Some([param(6), param(10), param(14)]) {
+        return Err(PassHubError::Decryption);
+    }
+
+    let salt = &header[18..];
+    let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+    let mut key = vault_key(&master_key, salt).ok_or(PassHubError::Decryption)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    key.zeroize();
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| PassHubError::Decryption)
+}
+
+// nonce || ciphertext under SHA-256 of the whole master key string
+fn open_legacy(data: &[u8], master_key: &str) -> Result<Vec<u8>> {
+    if data.len() < NONCE_LENGTH {
+        return Err(PassHubError::CorruptVault(
+            "The vault is too short".to_string(),
+        ));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+    let mut key: [u8; 32] = Sha256::digest(master_key.as_bytes()).into();
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    key.zeroize();
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| PassHubError::Decryption)
+}
+
+// Memory, iterations and lanes the master key was derived with
+fn argon2_params(master_key: &PasswordHash) -> Option<[u32; 3]> {
+    Some([
+        master_key.params.get_decimal("m")?,
+        master_key.params.get_decimal("t")?,
+        master_key.params.get_decimal("p")?,
+    ])
+}
+
+// AES key from the raw Argon2 output, the PHC string around it is not key material
+fn vault_key(master_key: &PasswordHash, salt: &[u8]) -> Option<[u8; 32]> {
+    let hash = master_key.hash?;
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(salt), hash.as_bytes())
+        .expand(HKDF_INFO, &mut key)
+        .ok()?;
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menue::api;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    use argon2::{Algorithm, Argon2, Params, Version};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde_json::json;
+
+    const PLAINTEXT: &[u8] = br#"[{"name":"example"}]"#;
+
+    // A PHC string like the login derives it, with cheap parameters
+    fn master_key(password: &str) -> String {
+        let params = Params::new(64, 1, 1, None).unwrap();
+        let salt = SaltString::encode_b64(b"passhub test salt").unwrap();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(password.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    // nonce || ciphertext as written before the header existed
+    fn seal_legacy(plaintext: &[u8], master_key: &str) -> Vec<u8> {
+        let key: [u8; 32] = Sha256::digest(master_key.as_bytes()).into();
+        let nonce = [7u8; NONCE_LENGTH];
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let mut data = nonce.to_vec();
+        data.extend(
+            cipher
+                .encrypt(Nonce::from_slice(&nonce), plaintext)
+                .unwrap(),
+        );
+        data
+    }
+
+    #[test]
+    fn seal_then_open() {
+        let master_key = master_key("Correct-Horse-9");
+        let data = seal(PLAINTEXT, &master_key).unwrap();
+        assert!(data.starts_with(MAGIC));
+        assert_eq!(data[4], VERSION);
+        assert_eq!(open(&data, &master_key).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn open_reads_legacy_vaults() {
+        let master_key = master_key("Correct-Horse-9");
+        let data = seal_legacy(PLAINTEXT, &master_key);
+        assert_eq!(open(&data, &master_key).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn open_rejects_changed_header_or_ciphertext() {
+        let master_key = master_key("Correct-Horse-9");
+        let data = seal(PLAINTEXT, &master_key).unwrap();
+        // HKDF salt, nonce and ciphertext
+        for offset in [20, HEADER_LENGTH, data.len() - 1] {
+            let mut changed = data.clone();
+            changed[offset] ^= 1;
+            assert!(
+                matches!(open(&changed, &master_key), Err(PassHubError::Decryption)),
+                "byte {}",
+                offset
+            );
+        }
+
+        let mut newer = data.clone();
+        newer[4] = VERSION + 1;
+        assert!(matches!(
+            open(&newer, &master_key),
+            Err(PassHubError::CorruptVault(_))
+        ));
+    }
+
+    #[test]
+    fn open_rejects_a_wrong_key() {
+        let master_key = master_key("Correct-Horse-9");
+        let wrong_key = self::master_key("Wrong-Horse-9");
+        let data = seal(PLAINTEXT, &master_key).unwrap();
+        assert!(matches!(
+            open(&data, &wrong_key),
+            Err(PassHubError::Decryption)
+        ));
+        let legacy = seal_legacy(PLAINTEXT, &master_key);
+        assert!(matches!(
+            open(&legacy, &wrong_key),
+            Err(PassHubError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn legacy_vault_is_saved_in_the_current_format() {
+        let master_key = master_key("Correct-Horse-9");
+        let legacy = STANDARD.encode(seal_legacy(PLAINTEXT, &master_key));
+        let vault = api::decrypt_vault(&legacy, &master_key).unwrap().unwrap();
+        assert_eq!(vault, json!([{ "name": "example" }]));
+
+        let saved = STANDARD
+            .decode(api::encrypt_vault(&vault, &master_key).unwrap())
+            .unwrap();
+        assert!(saved.starts_with(MAGIC));
+        assert!(open_versioned(&saved, &master_key).is_ok());
+        assert!(open_legacy(&saved, &master_key).is_err());
+    }
+}
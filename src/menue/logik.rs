@@ -1,76 +1,496 @@
-use super::{api, view};
+use super::{api, cache, clipboard, view};
+use crate::client::RsPassClient;
+use crate::error::PassHubError;
+use crate::{breach, export, import, lock, login, password_change, session, settings, sync};
+use chrono::{DateTime, Utc};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use log::debug;
 use serde::{Deserialize, Serialize};
 use serde_json::error::Category;
 use serde_json::Error;
 use serde_json::Value;
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use zeroize::Zeroize;
 
-pub fn main_menue(backend_url: &String, token: &String, mut password_hash: String) {
+pub fn main_menue(client: &RsPassClient, password_hash: String) {
+    // An expired session asks for the master password instead of dropping unsaved changes
+    let client = session::logik::renewable(client, lock::logik::Verifier::new(&password_hash));
+    vault_menue(&client, password_hash);
+    session::logik::forget();
+}
+
+fn vault_menue(client: &RsPassClient, mut password_hash: String) {
     loop {
-        // Get the passwords from the backend
-        let json_data_result = api::fetch(backend_url, token, &password_hash);
+        // Get the passwords from the backend and keep the encrypted blob for offline use
+        let json_data_result = api::fetch_encrypted(client).and_then(|remote| match remote {
+            Some(remote) => {
+                cache::save(&remote.encrypted_data);
+                api::decrypt_vault(&remote.encrypted_data, &password_hash)
+                    .map(|json| (json, remote.revision))
+            }
+            None => Ok((None, None)),
+        });
 
         match json_data_result {
-            Ok((200, Some(json_data))) => {
-                let entry_return = view::display_data(&json_data)
-                    .expect("Menue: Error occurred displaying the data");
-                let entries = entry_return.0;
-
-                let json_string =
-                    serialize_json(&entries).expect("Menue: Error occurred serializing the JSON");
-                let json_string = json_string.as_str();
-                let json_value: Value = match serde_json::from_str(json_string) {
-                    Ok(value) => value,
-                    Err(..) => panic!(),
+            Ok((Some(mut json_data), mut revision)) => {
+                let entry_return = match view::display_data(&json_data, false) {
+                    Ok(entry_return) => entry_return,
+                    Err(e) => {
+                        view::update_error(e.as_ref());
+                        clipboard::clear_now();
+                        password_hash.zeroize();
+                        return;
+                    }
                 };
-                _ = api::update(backend_url, token, &password_hash, &json_value);
-                if entry_return.1 {
-                    password_hash.zeroize();
-                    _ = api::logout(backend_url, token);
+                let mut entries = entry_return.0;
+
+                if !save_vault(
+                    client,
+                    &mut password_hash,
+                    &mut json_data,
+                    &mut entries,
+                    &mut revision,
+                ) {
+                    return;
                 }
-            }
-            Ok((200, None)) => {
-                let new_json: Entry = view::display_data_empty();
-                let new_json = vec![new_json];
-                let new_json = serialize_json(&new_json)
-                    .expect("Menue: Error occurred serializing the new JSON");
-                let json_string = new_json.as_str();
-                let new_json: Value = match serde_json::from_str(json_string) {
-                    Ok(value) => value,
-                    Err(..) => panic!(),
+                // Import and export hold decrypted data as well, they lock once idle too long
+                let lock_timeout = settings::logik::load().lock_timeout_seconds;
+                let lock = match entry_return.1 {
+                    MenueAction::Save => false,
+                    MenueAction::Logout => {
+                        clipboard::clear_now();
+                        breach::logik::forget();
+                        password_hash.zeroize();
+                        _ = api::logout(client);
+                        false
+                    }
+                    MenueAction::ChangePassword => {
+                        let mut idle_timer = lock::logik::IdleTimer::new(lock_timeout);
+                        if password_change::logik::change_master_password(client, &mut idle_timer) {
+                            // The old master key no longer decrypts the vault
+                            clipboard::clear_now();
+                            breach::logik::forget();
+                            password_hash.zeroize();
+                            _ = api::logout(client);
+                            return;
+                        }
+                        idle_timer.expired()
+                    }
+                    MenueAction::Import => {
+                        let mut idle_timer = lock::logik::IdleTimer::new(lock_timeout);
+                        import::logik::import(
+                            client,
+                            &password_hash,
+                            &mut entries,
+                            &mut revision,
+                            &mut idle_timer,
+                        );
+                        idle_timer.expired()
+                    }
+                    MenueAction::Export => {
+                        let mut idle_timer = lock::logik::IdleTimer::new(lock_timeout);
+                        export::logik::export(&entries, &mut idle_timer);
+                        idle_timer.expired()
+                    }
+                    MenueAction::Lock => true,
                 };
-                _ = api::update(backend_url, token, &password_hash, &new_json);
+                if lock {
+                    // Keep nothing decrypted in memory while locked
+                    entries.zeroize();
+                    zeroize_json(&mut json_data);
+                    if !lock_vault(client, &mut password_hash) {
+                        return;
+                    }
+                }
             }
-            Ok((401, _)) => {
-                view::update_error(401);
-                return;
+            Ok((None, _)) => {
+                let new_json = vec![view::display_data_empty()];
+                let saved = serde_json::to_value(&new_json)
+                    .map_err(PassHubError::from)
+                    .and_then(|new_json| api::update(client, &password_hash, &new_json, None));
+                if let Err(e) = saved {
+                    view::update_error(&e);
+                }
             }
-            Ok((500, _)) => {
-                view::update_error(500);
+            Err(e) if e.is_offline() => {
+                // Fall back to the cached vault if the backend went away
+                match cache::load().map(|data| api::decrypt_vault(&data, &password_hash)) {
+                    Some(Ok(Some(json_data))) => read_only_menue(&json_data),
+                    Some(Err(cache_error)) => view::update_error(&cache_error),
+                    _ => view::update_error(&e),
+                }
+                clipboard::clear_now();
+                password_hash.zeroize();
                 return;
             }
-            Ok((status_code, _)) => {
-                view::update_error(status_code as i16);
+            Err(e) => {
+                view::update_error(&e);
+                clipboard::clear_now();
+                password_hash.zeroize();
                 return;
             }
-            Err(_e) => {
-                view::update_error(418);
-                return;
+        }
+    }
+}
+
+// Upload the edited vault, json_data is the vault as fetched and the base of the merge.
+// Locking in the conflict prompt keeps the changes encrypted under the master key,
+// after unlocking the save starts over. False if the user logged out instead.
+fn save_vault(
+    client: &RsPassClient,
+    password_hash: &mut String,
+    json_data: &mut Value,
+    entries: &mut Vec<Entry>,
+    revision: &mut api::Revision,
+) -> bool {
+    let lock_timeout = settings::logik::load().lock_timeout_seconds;
+    loop {
+        // sync::logik::save wipes the base when done
+        let base = deserialize_json(json_data).unwrap_or_default();
+        let loaded = revision.clone();
+        let mut idle_timer = lock::logik::IdleTimer::new(lock_timeout);
+        let saved = sync::logik::save(
+            client,
+            password_hash,
+            base,
+            entries,
+            revision,
+            &mut |conflict| sync::view::resolve_conflict(conflict, &mut idle_timer),
+        );
+        // The conflict prompt or the session prompt timed out, lock the vault
+        match saved {
+            Ok(()) => return true,
+            Err(_) if idle_timer.expired() => {}
+            Err(e) => {
+                view::update_error(&e);
+                return true;
+            }
+        }
+
+        // Nothing has been saved, start over from the vault as fetched
+        *revision = loaded;
+        let sealed = seal_entries(entries, password_hash).and_then(|sealed_entries| {
+            Ok((
+                api::encrypt_vault(json_data, password_hash)?,
+                sealed_entries,
+            ))
+        });
+        entries.zeroize();
+        zeroize_json(json_data);
+        if !lock_vault(client, password_hash) {
+            return false;
+        }
+
+        let unsealed = sealed.and_then(|(sealed_base, sealed_entries)| {
+            let base = api::decrypt_vault(&sealed_base, password_hash)?;
+            let mut unsealed = api::decrypt_vault(&sealed_entries, password_hash)?;
+            let unsealed_entries = unsealed.as_ref().map(deserialize_json);
+            unsealed.iter_mut().for_each(zeroize_json);
+            match (base, unsealed_entries) {
+                (Some(base), Some(Ok(unsealed_entries))) => Ok((base, unsealed_entries)),
+                _ => Err(PassHubError::CorruptVault(
+                    "The unsaved changes could not be restored".to_string(),
+                )),
+            }
+        });
+        match unsealed {
+            Ok((base, unsealed_entries)) => {
+                *json_data = base;
+                *entries = unsealed_entries;
+            }
+            Err(e) => {
+                view::update_error(&e);
+                return true;
             }
         }
     }
 }
 
-pub fn get_uris(json_entries: Vec<Entry>) -> Result<Vec<String>, Error> {
-    //array für die Speicherung der uris anlegen
-    let mut uris = Vec::new();
-    for element in json_entries {
-        for uri in element.login.uris {
-            uris.push(uri.uri);
+fn seal_entries(entries: &[Entry], master_key: &str) -> Result<String, PassHubError> {
+    let mut json_data = serde_json::to_value(entries)?;
+    let sealed = api::encrypt_vault(&json_data, master_key);
+    zeroize_json(&mut json_data);
+    sealed
+}
+
+// Wipe the master key and wait for the master password, the caller wipes the entries.
+// False if the user logged out instead of unlocking.
+fn lock_vault(client: &RsPassClient, password_hash: &mut String) -> bool {
+    let verifier = lock::logik::Verifier::new(password_hash);
+    clipboard::clear_now();
+    breach::logik::forget();
+    password_hash.zeroize();
+    match lock::logik::unlock(&verifier) {
+        Some(master_key) => {
+            *password_hash = master_key;
+            true
+        }
+        None => {
+            _ = api::logout(client);
+            false
         }
     }
-    Ok(uris)
+}
+
+// Browse the cached vault while the backend is unreachable, edits are not possible
+pub fn offline_menue() {
+    if let Some(encrypted_data) = cache::load() {
+        let (mut master_key, json_data) = login::logik::login_offline(&encrypted_data);
+        read_only_menue(&json_data);
+        clipboard::clear_now();
+        master_key.zeroize();
+    }
+}
+
+// Show the cached vault read-only, exporting is still possible.
+// Locking ends the offline session, unlocking again goes through the offline login.
+fn read_only_menue(json_data: &Value) {
+    let lock_timeout = settings::logik::load().lock_timeout_seconds;
+    read_only_loop(
+        || view::display_data(json_data, true).ok(),
+        |entries| {
+            let mut idle_timer = lock::logik::IdleTimer::new(lock_timeout);
+            export::logik::export(entries, &mut idle_timer);
+            idle_timer.expired()
+        },
+    );
+    breach::logik::forget();
+}
+
+// Show the entry list again until the user logs out or locks.
+// export returns true if the vault has to be locked.
+fn read_only_loop(
+    mut display: impl FnMut() -> Option<(Vec<Entry>, MenueAction)>,
+    mut export: impl FnMut(&[Entry]) -> bool,
+) {
+    while let Some((mut entries, action)) = display() {
+        let leave = match action {
+            MenueAction::Export => export(&entries),
+            MenueAction::Logout | MenueAction::Lock => true,
+            // Closing an entry returns to the list, nothing is saved offline
+            _ => false,
+        };
+        entries.zeroize();
+        if leave {
+            break;
+        }
+    }
+}
+
+// What the user asked for when leaving the entry list
+#[derive(Debug, PartialEq)]
+pub enum MenueAction {
+    Save,
+    Logout,
+    ChangePassword,
+    Import,
+    Export,
+    Lock,
+}
+
+pub struct SearchHit {
+    pub index: usize,               // Position of the entry in the vault
+    pub name_positions: Vec<usize>, // Matched characters of the name for highlighting
+}
+
+// Fuzzy match the query against name, username, URI hosts and notes, best matches first
+pub fn search_entries(entries: &[Entry], query: &str) -> Vec<SearchHit> {
+    if query.is_empty() {
+        return (0..entries.len())
+            .map(|index| SearchHit {
+                index,
+                name_positions: Vec::new(),
+            })
+            .collect();
+    }
+
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let mut hits: Vec<(i64, SearchHit)> = Vec::new();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let (mut best_score, name_positions) = match matcher.fuzzy_indices(&entry.name, query) {
+            Some((score, positions)) => (Some(score), positions),
+            None => (None, Vec::new()),
+        };
+
+        let hosts = entry.login.uris.iter().map(|uri| uri_host(&uri.uri));
+        let other_fields = hosts
+            .chain(entry.login.username.clone())
+            .chain(entry.notes.clone());
+        for field in other_fields {
+            if let Some(score) = matcher.fuzzy_match(&field, query) {
+                best_score = Some(best_score.map_or(score, |best| best.max(score)));
+            }
+        }
+
+        if let Some(score) = best_score {
+            hits.push((
+                score,
+                SearchHit {
+                    index,
+                    name_positions,
+                },
+            ));
+        }
+    }
+
+    // Stable sort keeps the vault order for equal scores
+    hits.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    hits.into_iter().map(|(_, hit)| hit).collect()
+}
+
+// The part of the vault shown next to the folder sidebar
+#[derive(Debug, Clone, PartialEq)]
+pub enum FolderFilter {
+    All,
+    Recent, // Entries copied from, most recently used first
+    Unfiled,
+    Folder(String), // Includes the subfolders
+}
+
+// Length of the recently used section
+pub const RECENT_ENTRIES: usize = 10;
+
+impl FolderFilter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match (self, entry.folder.as_deref()) {
+            (FolderFilter::All, _) => true,
+            (FolderFilter::Recent, _) => entry.last_used.is_some(),
+            (FolderFilter::Unfiled, folder) => folder.is_none(),
+            (FolderFilter::Folder(_), None) => false,
+            (FolderFilter::Folder(filter), Some(folder)) => {
+                folder == filter
+                    || folder
+                        .strip_prefix(filter.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            }
+        }
+    }
+}
+
+// Search, then keep the hits in the selected folder that carry the selected tag
+pub fn filter_entries(
+    entries: &[Entry],
+    query: &str,
+    folder: &FolderFilter,
+    tag: Option<&str>,
+) -> Vec<SearchHit> {
+    let mut hits = search_entries(entries, query);
+    hits.retain(|hit| {
+        let entry = &entries[hit.index];
+        folder.matches(entry) && tag.is_none_or(|tag| entry.tags.iter().any(|t| t == tag))
+    });
+    hits
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Name,
+    Modified, // Newest first
+    LastUsed, // Most recently used first
+    Host,     // Of the first URI, entries without URI last
+}
+
+impl SortOrder {
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Modified => "last modified",
+            SortOrder::LastUsed => "last used",
+            SortOrder::Host => "URI host",
+        }
+    }
+
+    pub fn next(self) -> SortOrder {
+        match self {
+            SortOrder::Name => SortOrder::Modified,
+            SortOrder::Modified => SortOrder::LastUsed,
+            SortOrder::LastUsed => SortOrder::Host,
+            SortOrder::Host => SortOrder::Name,
+        }
+    }
+}
+
+// Sort the hits, the order of equal entries is kept
+pub fn sort_hits(entries: &[Entry], hits: &mut [SearchHit], order: SortOrder) {
+    match order {
+        SortOrder::Name => hits.sort_by_cached_key(|hit| entries[hit.index].name.to_lowercase()),
+        SortOrder::Modified => {
+            hits.sort_by_key(|hit| std::cmp::Reverse(entries[hit.index].modified))
+        }
+        SortOrder::LastUsed => {
+            hits.sort_by_key(|hit| std::cmp::Reverse(entries[hit.index].last_used))
+        }
+        SortOrder::Host => hits.sort_by_cached_key(|hit| {
+            let host = entries[hit.index]
+                .login
+                .uris
+                .first()
+                .map(|uri| uri_host(&uri.uri).to_lowercase());
+            (host.is_none(), host)
+        }),
+    }
+}
+
+// Every folder used in the vault including the parents of subfolders, sorted like a tree
+pub fn folders(entries: &[Entry]) -> Vec<String> {
+    let mut folders: Vec<String> = Vec::new();
+    for folder in entries.iter().filter_map(|entry| entry.folder.as_deref()) {
+        let mut path = String::new();
+        for part in folder.split('/') {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(part);
+            if !folders.contains(&path) {
+                folders.push(path.clone());
+            }
+        }
+    }
+    // Compare by path segment so subfolders follow their parent
+    folders.sort_by_key(|folder| folder.split('/').map(str::to_lowercase).collect::<Vec<_>>());
+    folders
+}
+
+pub fn tags(entries: &[Entry]) -> Vec<String> {
+    let mut tags: Vec<String> = entries
+        .iter()
+        .flat_map(|entry| entry.tags.iter().cloned())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags.sort_by_key(|tag| tag.to_lowercase());
+    tags
+}
+
+// Trim folder and tags after editing, empty ones are removed
+pub fn normalize_folder_and_tags(entry: &mut Entry) {
+    entry.folder = entry.folder.as_deref().and_then(|folder| {
+        let parts: Vec<&str> = folder
+            .split('/')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("/"))
+    });
+    let mut tags: Vec<String> = Vec::new();
+    for tag in entry.tags.drain(..) {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    entry.tags = tags;
+}
+
+fn uri_host(uri: &str) -> String {
+    match url::Url::parse(uri) {
+        Ok(url) => url.host_str().unwrap_or(uri).to_string(),
+        Err(_) => uri.to_string(),
+    }
 }
 
 pub fn deserialize_json(json_data: &Value) -> Result<Vec<Entry>, Error> {
@@ -98,40 +518,697 @@ pub fn deserialize_json(json_data: &Value) -> Result<Vec<Entry>, Error> {
     }
 }
 
-pub fn serialize_json(entries: &Vec<Entry>) -> Option<String> {
-    let json = match serde_json::to_string(&entries) {
-        Ok(value) => value,
-        Err(_) => {
-            view::serialization_error();
-            return None;
-        }
-    };
-    Some(json)
+// Overwrite all strings of a decrypted vault, serde_json does not zeroize on drop
+pub fn zeroize_json(value: &mut Value) {
+    match value {
+        Value::String(string) => string.zeroize(),
+        Value::Array(values) => values.iter_mut().for_each(zeroize_json),
+        Value::Object(map) => map.values_mut().for_each(zeroize_json),
+        _ => {}
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Zeroize)]
 pub struct Uri {
     pub uri: String,
+    // Stored as "match" like in Bitwarden, None means the default (domain)
+    #[serde(rename = "match", default, skip_serializing_if = "Option::is_none")]
+    #[zeroize(skip)]
+    pub match_type: Option<UriMatch>,
+}
+
+impl Uri {
+    pub fn new(uri: String) -> Self {
+        Uri {
+            uri,
+            match_type: None,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// How a URI is compared to a website, numbered like Bitwarden's UriMatchType
+#[derive(Debug, Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum UriMatch {
+    Domain = 0,
+    Host = 1,
+    StartsWith = 2,
+    Exact = 3,
+    RegularExpression = 4,
+    Never = 5,
+}
+
+impl UriMatch {
+    pub fn label(match_type: Option<UriMatch>) -> &'static str {
+        match match_type {
+            None => "default",
+            Some(UriMatch::Domain) => "domain",
+            Some(UriMatch::Host) => "host",
+            Some(UriMatch::StartsWith) => "starts with",
+            Some(UriMatch::Exact) => "exact",
+            Some(UriMatch::RegularExpression) => "regex",
+            Some(UriMatch::Never) => "never",
+        }
+    }
+
+    // Cycle through the match types, starting and ending with the default
+    pub fn next(match_type: Option<UriMatch>) -> Option<UriMatch> {
+        match match_type {
+            None => Some(UriMatch::Domain),
+            Some(UriMatch::Domain) => Some(UriMatch::Host),
+            Some(UriMatch::Host) => Some(UriMatch::StartsWith),
+            Some(UriMatch::StartsWith) => Some(UriMatch::Exact),
+            Some(UriMatch::Exact) => Some(UriMatch::RegularExpression),
+            Some(UriMatch::RegularExpression) => Some(UriMatch::Never),
+            Some(UriMatch::Never) => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Zeroize)]
 pub struct Login {
     pub uris: Vec<Uri>,
     pub username: Option<String>,
     pub password: String,
     pub totp: Option<String>,
+    // Replaced passwords, newest first
+    #[serde(
+        rename = "passwordHistory",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub password_history: Vec<PasswordHistory>,
+}
+
+// Like Bitwarden, only the last five passwords are kept
+const PASSWORD_HISTORY_LENGTH: usize = 5;
+
+impl Login {
+    // Keep the replaced password if the current one differs from it
+    pub fn record_password_change(&mut self, mut old_password: String) {
+        if old_password.is_empty() || old_password == self.password {
+            old_password.zeroize();
+            return;
+        }
+        self.password_history.insert(
+            0,
+            PasswordHistory {
+                password: old_password,
+                last_used_date: Utc::now(),
+            },
+        );
+        if self.password_history.len() > PASSWORD_HISTORY_LENGTH {
+            self.password_history
+                .drain(PASSWORD_HISTORY_LENGTH..)
+                .for_each(|mut old| old.zeroize());
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// Named like the password history of a Bitwarden item
+#[derive(Debug, Serialize, Deserialize, Clone, Zeroize)]
+pub struct PasswordHistory {
+    pub password: String,
+    // When the password was replaced
+    #[serde(rename = "lastUsedDate")]
+    #[zeroize(skip)]
+    pub last_used_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Zeroize)]
 
 pub struct Entry {
     pub id: String,
     pub name: String,
     pub notes: Option<String>,
+    // Vaults from before item types only have a login
+    #[serde(rename = "type", default)]
+    #[zeroize(skip)]
+    pub item_type: ItemType,
+    // Only used by login items, other types leave it empty.
+    // Always written, PassHub versions before item types require it.
+    #[serde(default)]
     pub login: Login,
+    #[serde(
+        rename = "secureNote",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub secure_note: Option<SecureNote>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card: Option<Card>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<Identity>,
+    #[serde(rename = "sshKey", default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<SshKey>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<Field>,
+    // Folder path, subfolders are separated by "/"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // Pinned to the top of the entry list
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[zeroize(skip)]
+    pub favorite: bool,
+    // Vaults from before timestamps have none, named like in Bitwarden
+    #[serde(
+        rename = "creationDate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[zeroize(skip)]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(
+        rename = "revisionDate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[zeroize(skip)]
+    pub modified: Option<DateTime<Utc>>,
+    // Last time a value of the entry was copied
+    #[serde(
+        rename = "lastUsedDate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[zeroize(skip)]
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl Entry {
+    pub fn new(item_type: ItemType) -> Self {
+        let mut entry = Entry {
+            id: generate_id(),
+            name: String::new(),
+            notes: None,
+            item_type: ItemType::Login,
+            login: Login::default(),
+            secure_note: None,
+            card: None,
+            identity: None,
+            ssh_key: None,
+            fields: Vec::new(),
+            folder: None,
+            tags: Vec::new(),
+            favorite: false,
+            created: Some(Utc::now()),
+            modified: Some(Utc::now()),
+            last_used: None,
+        };
+        entry.set_item_type(item_type);
+        entry
+    }
+
+    pub fn touch(&mut self) {
+        self.modified = Some(Utc::now());
+    }
+
+    // Switch the type and drop the data of all other types
+    pub fn set_item_type(&mut self, item_type: ItemType) {
+        self.item_type = item_type;
+        if item_type != ItemType::Login {
+            self.login.zeroize();
+        }
+        if item_type == ItemType::SecureNote {
+            self.secure_note.get_or_insert_with(SecureNote::default);
+        } else {
+            self.secure_note = None;
+        }
+        if item_type == ItemType::Card {
+            self.card.get_or_insert_with(Card::default);
+        } else {
+            self.card.zeroize();
+            self.card = None;
+        }
+        if item_type == ItemType::Identity {
+            self.identity.get_or_insert_with(Identity::default);
+        } else {
+            self.identity.zeroize();
+            self.identity = None;
+        }
+        if item_type == ItemType::SshKey {
+            self.ssh_key.get_or_insert_with(SshKey::default);
+        } else {
+            self.ssh_key.zeroize();
+            self.ssh_key = None;
+        }
+    }
+
+    // The type specific fields of items other than logins
+    pub fn payload(&self) -> Option<&dyn ItemPayload> {
+        match self.item_type {
+            ItemType::Login => None,
+            ItemType::SecureNote => self.secure_note.as_ref().map(|p| p as &dyn ItemPayload),
+            ItemType::Card => self.card.as_ref().map(|p| p as &dyn ItemPayload),
+            ItemType::Identity => self.identity.as_ref().map(|p| p as &dyn ItemPayload),
+            ItemType::SshKey => self.ssh_key.as_ref().map(|p| p as &dyn ItemPayload),
+        }
+    }
+
+    // Like payload, creates an empty one if the vault has none for the type
+    pub fn payload_mut(&mut self) -> Option<&mut dyn ItemPayload> {
+        match self.item_type {
+            ItemType::Login => None,
+            ItemType::SecureNote => Some(self.secure_note.get_or_insert_with(SecureNote::default)),
+            ItemType::Card => Some(self.card.get_or_insert_with(Card::default)),
+            ItemType::Identity => Some(self.identity.get_or_insert_with(Identity::default)),
+            ItemType::SshKey => Some(self.ssh_key.get_or_insert_with(SshKey::default)),
+        }
+    }
+}
+
+// Numbered like Bitwarden's CipherType
+#[derive(Debug, Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq, Default)]
+#[repr(u8)]
+pub enum ItemType {
+    #[default]
+    Login = 1,
+    SecureNote = 2,
+    Card = 3,
+    Identity = 4,
+    SshKey = 5,
+}
+
+impl ItemType {
+    pub const ALL: [ItemType; 5] = [
+        ItemType::Login,
+        ItemType::SecureNote,
+        ItemType::Card,
+        ItemType::Identity,
+        ItemType::SshKey,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ItemType::Login => "Login",
+            ItemType::SecureNote => "Secure Note",
+            ItemType::Card => "Card",
+            ItemType::Identity => "Identity",
+            ItemType::SshKey => "SSH Key",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = ItemType::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        ItemType::ALL[(index + 1) % ItemType::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        let index = ItemType::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        ItemType::ALL[(index + ItemType::ALL.len() - 1) % ItemType::ALL.len()]
+    }
+}
+
+// Form fields of a non-login item: label, whether the value is secret, and the values
+pub trait ItemPayload {
+    fn labels(&self) -> &'static [(&'static str, bool)];
+    fn values(&self) -> Vec<&Option<String>>;
+    fn values_mut(&mut self) -> Vec<&mut Option<String>>;
+}
+
+// The text of a secure note is kept in Entry.notes
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Zeroize)]
+pub struct SecureNote {
+    #[serde(rename = "type", default)]
+    pub note_type: u8, // Bitwarden only knows 0 (generic)
+}
+
+impl ItemPayload for SecureNote {
+    fn labels(&self) -> &'static [(&'static str, bool)] {
+        &[]
+    }
+    fn values(&self) -> Vec<&Option<String>> {
+        Vec::new()
+    }
+    fn values_mut(&mut self) -> Vec<&mut Option<String>> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Zeroize)]
+#[serde(rename_all = "camelCase")]
+pub struct Card {
+    pub cardholder_name: Option<String>,
+    pub brand: Option<String>,
+    pub number: Option<String>,
+    pub exp_month: Option<String>,
+    pub exp_year: Option<String>,
+    pub code: Option<String>,
+}
+
+impl ItemPayload for Card {
+    fn labels(&self) -> &'static [(&'static str, bool)] {
+        &[
+            ("Cardholder", false),
+            ("Brand", false),
+            ("Number", true),
+            ("Expiry month", false),
+            ("Expiry year", false),
+            ("Security code", true),
+        ]
+    }
+    fn values(&self) -> Vec<&Option<String>> {
+        vec![
+            &self.cardholder_name,
+            &self.brand,
+            &self.number,
+            &self.exp_month,
+            &self.exp_year,
+            &self.code,
+        ]
+    }
+    fn values_mut(&mut self) -> Vec<&mut Option<String>> {
+        vec![
+            &mut self.cardholder_name,
+            &mut self.brand,
+            &mut self.number,
+            &mut self.exp_month,
+            &mut self.exp_year,
+            &mut self.code,
+        ]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Zeroize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+    pub title: Option<String>,
+    pub first_name: Option<String>,
+    pub middle_name: Option<String>,
+    pub last_name: Option<String>,
+    pub company: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address1: Option<String>,
+    pub address2: Option<String>,
+    pub address3: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+    pub username: Option<String>,
+    pub ssn: Option<String>,
+    pub passport_number: Option<String>,
+    pub license_number: Option<String>,
+}
+
+impl ItemPayload for Identity {
+    fn labels(&self) -> &'static [(&'static str, bool)] {
+        &[
+            ("Title", false),
+            ("First name", false),
+            ("Middle name", false),
+            ("Last name", false),
+            ("Company", false),
+            ("E-Mail", false),
+            ("Phone", false),
+            ("Address 1", false),
+            ("Address 2", false),
+            ("Address 3", false),
+            ("City", false),
+            ("State", false),
+            ("Postal code", false),
+            ("Country", false),
+            ("Username", false),
+            ("Social security number", true),
+            ("Passport number", true),
+            ("License number", true),
+        ]
+    }
+    fn values(&self) -> Vec<&Option<String>> {
+        vec![
+            &self.title,
+            &self.first_name,
+            &self.middle_name,
+            &self.last_name,
+            &self.company,
+            &self.email,
+            &self.phone,
+            &self.address1,
+            &self.address2,
+            &self.address3,
+            &self.city,
+            &self.state,
+            &self.postal_code,
+            &self.country,
+            &self.username,
+            &self.ssn,
+            &self.passport_number,
+            &self.license_number,
+        ]
+    }
+    fn values_mut(&mut self) -> Vec<&mut Option<String>> {
+        vec![
+            &mut self.title,
+            &mut self.first_name,
+            &mut self.middle_name,
+            &mut self.last_name,
+            &mut self.company,
+            &mut self.email,
+            &mut self.phone,
+            &mut self.address1,
+            &mut self.address2,
+            &mut self.address3,
+            &mut self.city,
+            &mut self.state,
+            &mut self.postal_code,
+            &mut self.country,
+            &mut self.username,
+            &mut self.ssn,
+            &mut self.passport_number,
+            &mut self.license_number,
+        ]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Zeroize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshKey {
+    pub private_key: Option<String>,
+    pub public_key: Option<String>,
+    pub key_fingerprint: Option<String>,
+}
+
+impl ItemPayload for SshKey {
+    fn labels(&self) -> &'static [(&'static str, bool)] {
+        &[
+            ("Private key", true),
+            ("Public key", false),
+            ("Fingerprint", false),
+        ]
+    }
+    fn values(&self) -> Vec<&Option<String>> {
+        vec![&self.private_key, &self.public_key, &self.key_fingerprint]
+    }
+    fn values_mut(&mut self) -> Vec<&mut Option<String>> {
+        vec![
+            &mut self.private_key,
+            &mut self.public_key,
+            &mut self.key_fingerprint,
+        ]
+    }
+}
+
+// Custom field of an entry, serialized like Bitwarden's fields
+#[derive(Debug, Serialize, Deserialize, Clone, Zeroize)]
+pub struct Field {
+    pub name: String,
+    pub value: Option<String>, // "true"/"false" for booleans, None for linked fields
+    #[serde(rename = "type")]
+    #[zeroize(skip)]
+    pub field_type: FieldType,
+    #[serde(rename = "linkedId", default, skip_serializing_if = "Option::is_none")]
+    #[zeroize(skip)]
+    pub linked_id: Option<LinkedId>,
+}
+
+#[derive(Debug, Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum FieldType {
+    Text = 0,
+    Hidden = 1,
+    Boolean = 2,
+    Linked = 3,
+}
+
+// Login value a linked field refers to, numbered like Bitwarden's LoginLinkedId
+#[derive(Debug, Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq)]
+#[repr(u16)]
+pub enum LinkedId {
+    Username = 100,
+    Password = 101,
+}
+
+impl Default for Field {
+    fn default() -> Self {
+        Field {
+            name: String::new(),
+            value: Some(String::new()),
+            field_type: FieldType::Text,
+            linked_id: None,
+        }
+    }
+}
+
+impl Field {
+    // Change the type and reset the value to one that fits it
+    pub fn cycle_type(&mut self) {
+        self.field_type = match self.field_type {
+            FieldType::Text => FieldType::Hidden,
+            FieldType::Hidden => FieldType::Boolean,
+            FieldType::Boolean => FieldType::Linked,
+            FieldType::Linked => FieldType::Text,
+        };
+        match self.field_type {
+            FieldType::Hidden => {}
+            FieldType::Boolean => {
+                self.value.zeroize();
+                self.value = Some("false".to_string());
+            }
+            FieldType::Linked => {
+                self.value = None;
+                self.linked_id = Some(LinkedId::Password);
+            }
+            FieldType::Text => {
+                self.value = Some(String::new());
+                self.linked_id = None;
+            }
+        }
+    }
+
+    // Flip a boolean or switch the login value a linked field refers to
+    pub fn toggle(&mut self) {
+        match self.field_type {
+            FieldType::Boolean => {
+                let enabled = self.value.as_deref() == Some("true");
+                self.value = Some((!enabled).to_string());
+            }
+            FieldType::Linked => {
+                self.linked_id = match self.linked_id {
+                    Some(LinkedId::Password) => Some(LinkedId::Username),
+                    _ => Some(LinkedId::Password),
+                };
+            }
+            FieldType::Text | FieldType::Hidden => {}
+        }
+    }
+
+    // The value to copy, linked fields resolve to the login value
+    pub fn resolve(&self, login: &Login) -> Option<String> {
+        match (self.field_type, self.linked_id) {
+            (FieldType::Linked, Some(LinkedId::Username)) => login.username.clone(),
+            (FieldType::Linked, Some(LinkedId::Password)) => Some(login.password.clone()),
+            (FieldType::Linked, None) => None,
+            _ => self.value.clone(),
+        }
+    }
+}
+
+// Random UUID v4 as used by Bitwarden for entry ids
+pub fn generate_id() -> String {
+    use rand::Rng;
+
+    let mut bytes = [0u8; 16];
+    rand::rng().fill(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // Version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
 }
 
 pub fn validate_string_length(string: &str) -> bool {
     let capacity: usize = 200;
     string.len() <= capacity
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vaults_from_before_item_types_are_read_as_logins() {
+        let vault = serde_json::json!([{
+            "id": "4f2c3c1e-8a3b-4c1d-9e2f-0a1b2c3d4e5f",
+            "name": "Mail",
+            "notes": null,
+            "login": {
+                "uris": [{ "uri": "https://mail.example.com" }],
+                "username": "alice",
+                "password": "secret",
+                "totp": null
+            }
+        }]);
+        let entries = deserialize_json(&vault).unwrap();
+        assert_eq!(entries[0].item_type, ItemType::Login);
+        assert_eq!(entries[0].name, "Mail");
+        assert_eq!(entries[0].login.uris[0].uri, "https://mail.example.com");
+        assert_eq!(entries[0].login.username.as_deref(), Some("alice"));
+        assert_eq!(entries[0].login.password, "secret");
+        assert!(entries[0].tags.is_empty());
+        assert!(entries[0].created.is_none());
+    }
+
+    #[test]
+    fn other_item_types_stay_readable_for_older_versions() {
+        // The entry as PassHub read it before item types, the fields are only required
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct OldLogin {
+            uris: Vec<Uri>,
+            username: Option<String>,
+            password: String,
+            totp: Option<String>,
+        }
+        #[allow(dead_code)]
+        #[derive(Deserialize)]
+        struct OldEntry {
+            name: String,
+            login: OldLogin,
+        }
+
+        let note = Entry {
+            name: "Note".to_string(),
+            ..Entry::new(ItemType::SecureNote)
+        };
+        let vault = serde_json::to_value([note]).unwrap();
+        let old: Vec<OldEntry> = serde_json::from_value(vault).unwrap();
+        assert_eq!(old[0].name, "Note");
+    }
+
+    #[test]
+    fn offline_session_stays_open_after_closing_entries() {
+        // Two entries opened and closed, then logout
+        let mut actions = vec![MenueAction::Logout, MenueAction::Save, MenueAction::Save];
+        let mut shown = 0;
+        read_only_loop(
+            || {
+                shown += 1;
+                actions
+                    .pop()
+                    .map(|action| (vec![Entry::new(ItemType::Login)], action))
+            },
+            |_| false,
+        );
+        assert_eq!(shown, 3);
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn offline_session_ends_when_export_locks() {
+        let mut actions = vec![MenueAction::Save, MenueAction::Export];
+        read_only_loop(
+            || actions.pop().map(|action| (Vec::new(), action)),
+            |_| true,
+        );
+        assert_eq!(actions, vec![MenueAction::Save]);
+    }
+}
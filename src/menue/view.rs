@@ -1,4 +1,4 @@
-use copypasta::{ClipboardContext, ClipboardProvider};
+use chrono::{DateTime, Local, Utc};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
@@ -19,10 +19,24 @@ use serde_json::Value;
 use std::{
     error::Error,
     io::{self, stdout},
+    time::Duration,
     vec,
 };
 
-use super::logik::{self, get_uris, Entry, Login, Uri};
+use super::clipboard;
+use super::logik::{
+    self, Entry, Field, FieldType, FolderFilter, ItemPayload, ItemType, LinkedId, MenueAction,
+    SortOrder, Uri, UriMatch,
+};
+use crate::breach;
+use crate::error::PassHubError;
+use crate::generator;
+use crate::health;
+use crate::lock::logik::IdleTimer;
+use crate::session;
+use crate::settings;
+use crate::totp::logik::Totp;
+use zeroize::Zeroize;
 
 pub fn display_data_empty() -> Entry {
     // Setup terminal for error screen
@@ -55,8 +69,13 @@ pub fn display_data_empty() -> Entry {
         if let Event::Key(key_event) = event::read().unwrap() {
             if key_event.kind == KeyEventKind::Press {
                 if let KeyCode::Char('+') = key_event.code {
-                    new_entry = add_entry();
-                    break;
+                    // Locking only wipes the draft, the vault is still empty
+                    let lock_timeout = settings::logik::load().lock_timeout_seconds;
+                    if let Some(entry) = add_entry(&mut IdleTimer::new(lock_timeout)) {
+                        new_entry = entry;
+                        break;
+                    }
+                    terminal.clear().unwrap();
                 }
             }
         }
@@ -64,46 +83,24 @@ pub fn display_data_empty() -> Entry {
     new_entry
 }
 
-pub fn display_data(json_data: &Value) -> Result<(Vec<Entry>, bool), Box<dyn Error>> {
-    let entries = super::logik::deserialize_json(json_data);
-    let entries = match entries {
-        Ok(e) => e,
-        Err(..) => {
-            debug!("There was an error while parsing JSON");
-            panic!()
-        }
-    };
-    let entries_2 = entries.clone();
-    let uris = get_uris(entries);
-    match uris {
-        Ok(_) => display_uris(entries_2),
-        Err(e) => {
-            debug!("Error while parsing JSON!");
-            Err(Box::new(e))
-        }
-    }
+pub fn display_data(
+    json_data: &Value,
+    read_only: bool,
+) -> Result<(Vec<Entry>, MenueAction), Box<dyn Error>> {
+    let entries = super::logik::deserialize_json(json_data).map_err(|e| {
+        debug!("There was an error while parsing JSON");
+        PassHubError::CorruptVault(e.to_string())
+    })?;
+    display_uris(entries, read_only)
 }
 
-pub fn add_entry() -> Entry {
-    let mut new_entry = Entry {
-        id: String::new(),
-        name: String::new(),
-        notes: None,
-        login: Login {
-            uris: vec![Uri { uri: String::new() }],
-            username: None,
-            password: String::new(),
-            totp: None,
-        },
-    };
+// None if the vault has to be locked before the entry is complete, idle_timer tells
+pub fn add_entry(idle_timer: &mut IdleTimer) -> Option<Entry> {
+    let mut new_entry = Entry::new(ItemType::Login);
+    new_entry.login.uris.push(Uri::new(String::new()));
 
-    let mut popup_fields = StatefulList::new(vec![
-        "Name".to_string(),
-        "URI".to_string(),
-        "Username".to_string(),
-        "Password".to_string(),
-        "Notes".to_string(),
-    ]);
+    let mut popup_fields = StatefulList::new(add_form_labels(&new_entry));
+    popup_fields.state.select(Some(1)); // Start with the name
 
     let stdout = io::stdout();
     let backend = CrosstermBackend::new(stdout);
@@ -111,6 +108,9 @@ pub fn add_entry() -> Entry {
     terminal.clear().unwrap();
 
     loop {
+        let selected = popup_fields.state.selected().unwrap_or(0);
+        let notes_row = popup_fields.items.len() - 1;
+
         // Render the popup
         terminal
             .draw(|f| {
@@ -134,7 +134,11 @@ pub fn add_entry() -> Entry {
                             .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
                             .title("Add New Entry")
                             .title_style(Style::default().add_modifier(Modifier::BOLD))
-                            .title_bottom("Navigate (arrow keys), Edit (type), Save (Enter)"),
+                            .title_bottom(if new_entry.item_type == ItemType::Login {
+                                "Navigate (arrow keys), Change type (Left/Right), Edit (type), Generate password (CTRL+G), URI match type (CTRL+U), Save (Enter)"
+                            } else {
+                                "Navigate (arrow keys), Change type (Left/Right), Edit (type), Save (Enter)"
+                            }),
                     )
                     .highlight_style(Style::default().fg(Color::Rgb(255, 163, 26)))
                     .highlight_symbol(">> ");
@@ -142,14 +146,7 @@ pub fn add_entry() -> Entry {
                 f.render_stateful_widget(list, chunks[0], &mut popup_fields.state);
 
                 // Render the content of the selected field for editing
-                let field_content = match popup_fields.state.selected() {
-                    Some(0) => &new_entry.name,
-                    Some(1) => &new_entry.login.uris[0].uri,
-                    Some(2) => new_entry.login.username.as_deref().unwrap_or("(none)"),
-                    Some(3) => &new_entry.login.password,
-                    Some(4) => new_entry.notes.as_deref().unwrap_or("(none)"),
-                    _ => "",
-                };
+                let field_content = add_form_content(&new_entry, selected);
 
                 let paragraph = Paragraph::new(field_content).block(
                     Block::default()
@@ -163,18 +160,37 @@ pub fn add_entry() -> Entry {
             .unwrap();
 
         // Handle user input in the popup
-        if let Event::Key(key) = event::read().unwrap() {
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => {
+                new_entry.zeroize();
+                terminal.clear().unwrap();
+                return None;
+            }
+        };
+        if let Event::Key(key) = event {
             if key.kind == KeyEventKind::Press {
                 match key.code {
                     KeyCode::Up => popup_fields.previous(),
                     KeyCode::Down => popup_fields.next(),
+                    // The first row selects the item type, the form follows it
+                    KeyCode::Left | KeyCode::Right if selected == 0 => {
+                        let item_type = if key.code == KeyCode::Left {
+                            new_entry.item_type.previous()
+                        } else {
+                            new_entry.item_type.next()
+                        };
+                        new_entry.set_item_type(item_type);
+                        if item_type == ItemType::Login {
+                            new_entry.login.uris.push(Uri::new(String::new()));
+                        }
+                        popup_fields.items = add_form_labels(&new_entry);
+                    }
+                    KeyCode::Enter if selected == notes_row => {
+                        new_entry.notes.get_or_insert_with(String::new).push('\n');
+                    }
                     KeyCode::Enter => {
-                        if popup_fields.state.selected() == Some(4) {
-                            if new_entry.notes.is_none() {
-                                new_entry.notes = Some(String::new());
-                            }
-                            new_entry.notes.as_mut().unwrap().push('\n');
-                        } else if new_entry.name.is_empty() {
+                        if new_entry.name.is_empty() {
                             // Ensure the name field is mandatory
                             terminal
                                 .draw(|f| {
@@ -187,74 +203,50 @@ pub fn add_entry() -> Entry {
                                 .unwrap();
                             std::thread::sleep(std::time::Duration::from_secs(2));
                         } else {
+                            // More URIs can be added in the entry popup
+                            new_entry.login.uris.retain(|uri| !uri.uri.is_empty());
                             break; // Exit if name is not empty
                         }
+                    } // Finalize entry
+                    KeyCode::Char('g')
+                        if key.modifiers == KeyModifiers::CONTROL
+                            && new_entry.item_type == ItemType::Login =>
+                    {
+                        // Replace the password with a generated one
+                        if let Some(password) = generator::view::draw_generator(idle_timer) {
+                            new_entry.login.password.zeroize();
+                            new_entry.login.password = password;
+                            popup_fields.state.select(Some(4));
+                        }
+                        terminal.clear().unwrap();
                     }
-
-                    , // Finalize entry
-                    KeyCode::Char(c) => {
+                    KeyCode::Char('l') if key.modifiers == KeyModifiers::CONTROL => {
+                        idle_timer.lock_now();
+                    }
+                    KeyCode::Char('u')
+                        if key.modifiers == KeyModifiers::CONTROL
+                            && new_entry.item_type == ItemType::Login
+                            && selected == 2 =>
+                    {
+                        let uri = &mut new_entry.login.uris[0];
+                        uri.match_type = UriMatch::next(uri.match_type);
+                    }
+                    // Unhandled Ctrl shortcuts are no text, AltGr sends Ctrl+Alt
+                    KeyCode::Char(c)
+                        if !key.modifiers.contains(KeyModifiers::CONTROL)
+                            || key.modifiers.contains(KeyModifiers::ALT) =>
+                    {
                         // Edit the selected field
-                        match popup_fields.state.selected() {
-                            Some(0) => {
-                                if logik::validate_string_length(&new_entry.name) {
-                                    new_entry.name.push(c)
-                                }
-                            }
-                            Some(1) => {
-                                if logik::validate_string_length(&new_entry.login.uris[0].uri) {
-                                    new_entry.login.uris[0].uri.push(c)
-                                }
-                            }
-                            Some(2) => {
-                                if new_entry.login.username.is_none() {
-                                    new_entry.login.username = Some(String::new());
-                                }
-                                if logik::validate_string_length(
-                                    new_entry.login.username.as_ref().unwrap(),
-                                ) {
-                                    new_entry.login.username.as_mut().unwrap().push(c);
-                                }
-                            }
-                            Some(3) => {
-                                if logik::validate_string_length(&new_entry.login.password) {
-                                    new_entry.login.password.push(c)
-                                }
-                            }
-                            Some(4) => {
-                                if new_entry.notes.is_none() {
-                                    new_entry.notes = Some(String::new());
-                                }
-                                if logik::validate_string_length(new_entry.notes.as_ref().unwrap())
-                                {
-                                    new_entry.notes.as_mut().unwrap().push(c);
-                                }
+                        if let Some(text) = add_form_text(&mut new_entry, selected) {
+                            if logik::validate_string_length(text) {
+                                text.push(c);
                             }
-                            _ => {}
                         }
                     }
                     KeyCode::Backspace => {
                         // Handle deletion of characters in the selected field
-                        match popup_fields.state.selected() {
-                            Some(0) => {
-                                new_entry.name.pop();
-                            }
-                            Some(1) => {
-                                new_entry.login.uris[0].uri.pop();
-                            }
-                            Some(2) => {
-                                if let Some(username) = new_entry.login.username.as_mut() {
-                                    username.pop();
-                                }
-                            }
-                            Some(3) => {
-                                new_entry.login.password.pop();
-                            }
-                            Some(4) => {
-                                if let Some(notes) = new_entry.notes.as_mut() {
-                                    notes.pop();
-                                }
-                            }
-                            _ => {}
+                        if let Some(text) = add_form_text(&mut new_entry, selected) {
+                            text.pop();
                         }
                     }
                     _ => {}
@@ -265,16 +257,94 @@ pub fn add_entry() -> Entry {
 
     terminal.clear().unwrap();
 
-    new_entry
+    Some(new_entry)
 }
 
-pub fn display_uris(mut entries: Vec<Entry>) -> Result<(Vec<Entry>, bool), Box<dyn Error>> {
-    let mut to_save: bool = false;
-    let name_list = entries
-        .iter()
-        .map(|item| item.name.clone())
-        .collect::<Vec<_>>();
-    let mut stateful_list = StatefulList::new(name_list);
+// Rows of the add form: type, name, the fields of the type and notes
+fn add_form_labels(entry: &Entry) -> Vec<String> {
+    let mut labels = vec![
+        format!("Type: < {} >", entry.item_type.label()),
+        "Name".to_string(),
+    ];
+    match entry.payload() {
+        None => labels.extend(["URI", "Username", "Password"].map(String::from)),
+        Some(payload) => labels.extend(payload.labels().iter().map(|(label, _)| label.to_string())),
+    }
+    labels.push("Notes".to_string());
+    labels
+}
+
+fn add_form_content(entry: &Entry, row: usize) -> String {
+    let type_rows = match entry.payload() {
+        None => 3,
+        Some(payload) => payload.labels().len(),
+    };
+    let value = match (row, entry.payload()) {
+        (0, _) => Some(entry.item_type.label().to_string()),
+        (1, _) => Some(entry.name.clone()),
+        (row, _) if row == type_rows + 2 => entry.notes.clone(),
+        (2, None) => Some(format!(
+            "{}\nMatch: {}",
+            entry.login.uris[0].uri,
+            UriMatch::label(entry.login.uris[0].match_type)
+        )),
+        (3, None) => entry.login.username.clone(),
+        (4, None) => Some(entry.login.password.clone()),
+        (row, Some(payload)) => payload.values().get(row - 2).and_then(|v| (*v).clone()),
+        _ => None,
+    };
+    value.unwrap_or_else(|| "(none)".to_string())
+}
+
+// The text edited in a row of the add form, None for the type row
+fn add_form_text(entry: &mut Entry, row: usize) -> Option<&mut String> {
+    let type_rows = match entry.payload() {
+        None => 3,
+        Some(payload) => payload.labels().len(),
+    };
+    match row {
+        0 => None,
+        1 => Some(&mut entry.name),
+        row if row == type_rows + 2 => Some(entry.notes.get_or_insert_with(String::new)),
+        2 if entry.item_type == ItemType::Login => Some(&mut entry.login.uris[0].uri),
+        3 if entry.item_type == ItemType::Login => {
+            Some(entry.login.username.get_or_insert_with(String::new))
+        }
+        _ if entry.item_type == ItemType::Login => Some(&mut entry.login.password),
+        row => entry
+            .payload_mut()?
+            .values_mut()
+            .into_iter()
+            .nth(row - 2)
+            .map(|value| value.get_or_insert_with(String::new)),
+    }
+}
+
+pub fn display_uris(
+    mut entries: Vec<Entry>,
+    read_only: bool,
+) -> Result<(Vec<Entry>, MenueAction), Box<dyn Error>> {
+    let mut action = MenueAction::Save;
+    let mut search_query = String::new();
+    let mut search_mode = false; // Typing goes into the search bar
+    let mut folder_filters = sidebar_filters(&entries);
+    let mut sidebar = StatefulList::new(sidebar_names(&entries, &folder_filters));
+    sidebar.state.select(Some(0));
+    let mut sidebar_focus = false; // Arrow keys move through the folders
+    let mut tag_filter: Option<String> = None;
+    let mut move_input: Option<String> = None; // Folder typed while moving an entry
+    let mut sort_order = settings::logik::load().sort_order;
+    let mut hits = visible_hits(
+        &entries,
+        &search_query,
+        &FolderFilter::All,
+        None,
+        sort_order,
+    );
+    let mut stateful_list = StatefulList::new(hit_names(&entries, &hits));
+    let lock_timeout = settings::logik::load().lock_timeout_seconds;
+    let mut idle_timer = IdleTimer::new(lock_timeout);
+    breach::logik::check_entries(&entries);
 
     // Initializing the terminal with CrosstermBackend
     let stdout = stdout();
@@ -283,101 +353,360 @@ pub fn display_uris(mut entries: Vec<Entry>) -> Result<(Vec<Entry>, bool), Box<d
     terminal.clear().unwrap();
     enable_raw_mode().unwrap();
 
-    let mut show_popup = false;
-    let mut selected_index; // Track whether the popup is displayed
-    let mut popup: Option<PasswordPopup> = None; // Track selected entry index
-
     loop {
         terminal.draw(|f| {
-            let size = f.area();
+            let (size, status_area) = status_area(f.area());
+            let show_search = search_mode || !search_query.is_empty();
+            let show_bar = show_search || move_input.is_some();
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Percentage(100)].as_ref())
+                .constraints(if show_bar {
+                    vec![Constraint::Min(0), Constraint::Length(3)]
+                } else {
+                    vec![Constraint::Percentage(100)]
+                })
                 .split(size);
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(28), Constraint::Min(0)])
+                .split(chunks[0]);
 
-            // Render the list if no popup is active
-            if !show_popup {
-                let list_items: Vec<ListItem> = stateful_list
-                    .items
-                    .iter()
-                    .map(|i| ListItem::new(i.as_str()))
-                    .collect();
+            let folder_list = List::new(sidebar.items.iter().map(|name| ListItem::new(name.as_str())))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(if sidebar_focus {
+                            Style::default().fg(Color::Rgb(255, 163, 26))
+                        } else {
+                            Style::default()
+                        })
+                        .title("Folders")
+                        .title_bottom("Switch (TAB)"),
+                )
+                .highlight_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                .highlight_symbol("> ");
+            f.render_stateful_widget(folder_list, columns[0], &mut sidebar.state);
 
-                let list = List::new(list_items)
-                    .block(ratatui::widgets::Block::default()
-                    .borders(ratatui::widgets::Borders::ALL)
-                    .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
-                    .title("PassHub")
-                    .title_bottom("Add entry (+), delete entry (DEL), save and log out (ESC), navigate (arrow keys)")
-                    .title_style(Style::default()
-                    .add_modifier(Modifier::BOLD)))
-                    .highlight_style(Style::default().fg(Color::Rgb(255, 163, 26)))
-                    .highlight_symbol(">> ");
+            let list_items: Vec<ListItem> = stateful_list
+                .items
+                .iter()
+                .zip(hits.iter())
+                .map(|(name, hit)| {
+                    let mut line = highlight_matches(name, &hit.name_positions);
+                    if entries[hit.index].favorite {
+                        line.spans
+                            .insert(0, Span::styled("★ ", Style::default().fg(Color::Rgb(255, 163, 26))));
+                    }
+                    if is_breached(&entries[hit.index]) {
+                        line.spans.insert(0, Span::styled("⚠ ", Style::default().fg(Color::Red)));
+                    }
+                    ListItem::new(line)
+                })
+                .collect();
 
-                f.render_stateful_widget(list, chunks[0], &mut stateful_list.state);
-            } else if let Some(popup) = &popup{
-                // Render the popup
-                let area = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints(vec![
-                        Constraint::Percentage(30),
-                        Constraint::Percentage(70),
-                    ])
-                    .split(f.area());
+            let list = List::new(list_items)
+                .block(ratatui::widgets::Block::default()
+                .borders(ratatui::widgets::Borders::ALL)
+                .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                .title(match (&tag_filter, read_only) {
+                    (Some(tag), true) => format!("PassHub (offline, read-only) #{}", tag),
+                    (Some(tag), false) => format!("PassHub #{}", tag),
+                    (None, true) => "PassHub (offline, read-only)".to_string(),
+                    (None, false) => "PassHub".to_string(),
+                })
+                .title(Line::from(format!("Sorted by {} (s)", sort_order.label())).right_aligned())
+                .title_bottom(if read_only {
+                    "Search (/), sort (s), filter by tag (CTRL+T), vault health (h), export (CTRL+E), lock (CTRL+L), close (ESC), navigate (arrow keys)"
+                } else {
+                    "Add entry (+), delete entry (DEL), favorite (f), move to folder (m), search (/), sort (s), filter by tag (CTRL+T), vault health (h), import (CTRL+O), export (CTRL+E), lock (CTRL+L), change master password (CTRL+P), save and log out (ESC), navigate (arrow keys)"
+                })
+                .title_style(Style::default()
+                .add_modifier(Modifier::BOLD)))
+                .highlight_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                .highlight_symbol(">> ");
+
+            f.render_stateful_widget(list, columns[1], &mut stateful_list.state);
 
-                //f.render_widget(Clear, area[1]); // Clear the background
-                popup.render(area[1], f.buffer_mut()); // Render the popup
+            if let Some(folder) = &move_input {
+                let move_bar = Paragraph::new(folder.as_str()).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                        .title("Move to folder, subfolders are separated by /")
+                        .title_bottom("Next existing folder (TAB), move (Enter), cancel (ESC)"),
+                );
+                f.render_widget(move_bar, chunks[1]);
+            } else if show_search {
+                let search_bar = Paragraph::new(format!("/{}", search_query)).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(if search_mode {
+                            Style::default().fg(Color::Rgb(255, 163, 26))
+                        } else {
+                            Style::default()
+                        })
+                        .title(format!("Search ({} of {})", hits.len(), entries.len()))
+                        .title_bottom(if search_mode {
+                            "Apply filter (Enter), clear (ESC)"
+                        } else {
+                            "Edit search (/), clear (ESC)"
+                        }),
+                );
+                f.render_widget(search_bar, chunks[1]);
             }
+            render_status(status_area, f.buffer_mut());
         })?;
 
+        // Redraw periodically so the clipboard countdown stays live
+        if !event::poll(Duration::from_millis(250))? {
+            if idle_timer.expired() {
+                action = MenueAction::Lock;
+                break;
+            }
+            continue;
+        }
+
         // Code for user input handling
         if let event::Event::Key(key) = event::read().unwrap() {
             if key.kind == KeyEventKind::Press {
-                if show_popup {
-                    if let Some(popup) = popup.as_mut() {
-                        match key.code {
-                            KeyCode::Esc => {
-                                break;
-                            }
-                            KeyCode::Tab => {
-                                popup.edit_mode = match popup.edit_mode {
-                                    EditMode::None => EditMode::Uri,
-                                    EditMode::Uri => EditMode::Password,
-                                    EditMode::Password => EditMode::Username,
-                                    EditMode::Note => EditMode::Name,
-                                    EditMode::Username => EditMode::Note,
-                                    EditMode::Name => EditMode::Uri,
-                                };
+                idle_timer.reset();
+                // Ctrl+L locks from every mode, also while typing a search or folder
+                if key.code == KeyCode::Char('l') && key.modifiers == KeyModifiers::CONTROL {
+                    action = MenueAction::Lock;
+                    break;
+                }
+                if let Some(folder) = move_input.as_mut() {
+                    match key.code {
+                        KeyCode::Tab => *folder = next_folder(&entries, folder),
+                        KeyCode::Backspace => {
+                            folder.pop();
+                        }
+                        KeyCode::Char(c) if logik::validate_string_length(folder) => folder.push(c),
+                        KeyCode::Enter => {
+                            if let Some(hit) = stateful_list
+                                .get_selected_index()
+                                .and_then(|selected| hits.get(selected))
+                            {
+                                let entry = &mut entries[hit.index];
+                                let previous = entry.folder.take();
+                                entry.folder = Some(folder.clone());
+                                logik::normalize_folder_and_tags(entry);
+                                if entry.folder != previous {
+                                    entry.touch();
+                                }
                             }
-                            _ => popup.handle_input(key.code, key.modifiers),
+                            move_input = None;
+                            folder_filters = sidebar_filters(&entries);
+                            sidebar.items = sidebar_names(&entries, &folder_filters);
+                            hits = visible_hits(
+                                &entries,
+                                &search_query,
+                                &selected_filter(&sidebar, &folder_filters),
+                                tag_filter.as_deref(),
+                                sort_order,
+                            );
+                            stateful_list.set_items(hit_names(&entries, &hits));
+                        }
+                        KeyCode::Esc => move_input = None,
+                        _ => {}
+                    }
+                } else if sidebar_focus && !search_mode {
+                    match key.code {
+                        KeyCode::Down => sidebar.next(),
+                        KeyCode::Up => sidebar.previous(),
+                        KeyCode::Tab | KeyCode::Right | KeyCode::Enter => sidebar_focus = false,
+                        KeyCode::Esc => {
+                            sidebar.state.select(Some(0));
+                            sidebar_focus = false;
+                        }
+                        _ => {}
+                    }
+                    hits = visible_hits(
+                        &entries,
+                        &search_query,
+                        &selected_filter(&sidebar, &folder_filters),
+                        tag_filter.as_deref(),
+                        sort_order,
+                    );
+                    stateful_list.set_items(hit_names(&entries, &hits));
+                } else if search_mode {
+                    match key.code {
+                        KeyCode::Down => stateful_list.next(),
+                        KeyCode::Up => stateful_list.previous(),
+                        KeyCode::Enter => search_mode = false,
+                        KeyCode::Esc => {
+                            search_mode = false;
+                            search_query.clear();
+                        }
+                        KeyCode::Backspace => {
+                            search_query.pop();
+                        }
+                        KeyCode::Char(c)
+                            if !key.modifiers.contains(KeyModifiers::CONTROL)
+                                || key.modifiers.contains(KeyModifiers::ALT) =>
+                        {
+                            search_query.push(c)
                         }
+                        _ => {}
                     }
+                    hits = visible_hits(
+                        &entries,
+                        &search_query,
+                        &selected_filter(&sidebar, &folder_filters),
+                        tag_filter.as_deref(),
+                        sort_order,
+                    );
+                    stateful_list.set_items(hit_names(&entries, &hits));
                 } else {
                     match key.code {
                         KeyCode::Down => stateful_list.next(),
                         KeyCode::Up => stateful_list.previous(),
+                        KeyCode::Char('/') => search_mode = true,
+                        KeyCode::Tab | KeyCode::Left => sidebar_focus = true,
+                        KeyCode::Char('s') => {
+                            sort_order = sort_order.next();
+                            settings::logik::save_sort_order(sort_order);
+                            hits = visible_hits(
+                                &entries,
+                                &search_query,
+                                &selected_filter(&sidebar, &folder_filters),
+                                tag_filter.as_deref(),
+                                sort_order,
+                            );
+                            stateful_list.set_items(hit_names(&entries, &hits));
+                        }
+                        KeyCode::Char('t') if key.modifiers == KeyModifiers::CONTROL => {
+                            tag_filter = next_tag(&entries, tag_filter.as_deref());
+                            hits = visible_hits(
+                                &entries,
+                                &search_query,
+                                &selected_filter(&sidebar, &folder_filters),
+                                tag_filter.as_deref(),
+                                sort_order,
+                            );
+                            stateful_list.set_items(hit_names(&entries, &hits));
+                        }
+                        KeyCode::Char('f') if !read_only => {
+                            if let Some(hit) = stateful_list
+                                .get_selected_index()
+                                .and_then(|selected| hits.get(selected))
+                            {
+                                let index = hit.index;
+                                entries[index].favorite = !entries[index].favorite;
+                                entries[index].touch();
+                                hits = visible_hits(
+                                    &entries,
+                                    &search_query,
+                                    &selected_filter(&sidebar, &folder_filters),
+                                    tag_filter.as_deref(),
+                                    sort_order,
+                                );
+                                stateful_list.items = hit_names(&entries, &hits);
+                                // Keep the toggled entry selected after it moved
+                                stateful_list
+                                    .state
+                                    .select(hits.iter().position(|hit| hit.index == index));
+                            }
+                        }
+                        KeyCode::Char('m') if !read_only => {
+                            if let Some(hit) = stateful_list
+                                .get_selected_index()
+                                .and_then(|selected| hits.get(selected))
+                            {
+                                move_input =
+                                    Some(entries[hit.index].folder.clone().unwrap_or_default());
+                            }
+                        }
                         KeyCode::Enter => {
-                            selected_index = stateful_list.state.selected().unwrap_or(0);
-                            popup = Some(PasswordPopup::from_entry(&mut entries[selected_index]));
-                            show_popup = true; // Show the popup
+                            let selected = stateful_list.state.selected().unwrap_or(0);
+                            if let Some(hit) = hits.get(selected) {
+                                let entry = &mut entries[hit.index];
+                                if open_entry(&mut terminal, entry, read_only, lock_timeout)? {
+                                    action = MenueAction::Lock;
+                                }
+                                break; // Leaving the popup saves the entry
+                            }
+                        }
+                        KeyCode::Char('h') => {
+                            let max_age_days = settings::logik::load().password_max_age_days;
+                            let report = health::logik::audit(&entries, max_age_days);
+                            let jump = health::view::draw_report(
+                                &entries,
+                                &report,
+                                max_age_days,
+                                &mut idle_timer,
+                            );
+                            terminal.clear()?;
+                            if idle_timer.expired() {
+                                action = MenueAction::Lock;
+                                break;
+                            }
+                            if let Some(index) = jump {
+                                let entry = &mut entries[index];
+                                if open_entry(&mut terminal, entry, read_only, lock_timeout)? {
+                                    action = MenueAction::Lock;
+                                }
+                                break;
+                            }
                         }
-                        KeyCode::Char('+') => {
+                        KeyCode::Char('+') if !read_only => {
                             terminal.clear()?;
-                            let new_entry: Entry = add_entry();
+                            let mut new_entry: Entry = match add_entry(&mut idle_timer) {
+                                Some(new_entry) => new_entry,
+                                None => {
+                                    action = MenueAction::Lock;
+                                    break;
+                                }
+                            };
+                            // New entries go into the folder that is shown
+                            if let FolderFilter::Folder(folder) =
+                                selected_filter(&sidebar, &folder_filters)
+                            {
+                                new_entry.folder = Some(folder);
+                            }
                             let new_entry_name = new_entry.name.clone();
                             entries.push(new_entry);
                             stateful_list.items.push(new_entry_name);
                             break;
                         }
-                        KeyCode::Delete => {
+                        KeyCode::Delete if !read_only => {
                             if let Some(index) = stateful_list.get_selected_index() {
                                 stateful_list.delete_selected();
-                                entries.remove(index);
+                                entries.remove(hits[index].index);
                                 break;
                             };
                         }
+                        KeyCode::Esc if !search_query.is_empty() || tag_filter.is_some() => {
+                            // Clear an applied filter before logging out
+                            search_query.clear();
+                            tag_filter = None;
+                            hits = visible_hits(
+                                &entries,
+                                &search_query,
+                                &selected_filter(&sidebar, &folder_filters),
+                                None,
+                                sort_order,
+                            );
+                            stateful_list.set_items(hit_names(&entries, &hits));
+                        }
+                        KeyCode::Char('p')
+                            if key.modifiers == KeyModifiers::CONTROL && !read_only =>
+                        {
+                            action = MenueAction::ChangePassword;
+                            break;
+                        }
+                        KeyCode::Char('o')
+                            if key.modifiers == KeyModifiers::CONTROL && !read_only =>
+                        {
+                            action = MenueAction::Import;
+                            break;
+                        }
+                        KeyCode::Char('e') if key.modifiers == KeyModifiers::CONTROL => {
+                            action = MenueAction::Export;
+                            break;
+                        }
                         KeyCode::Esc => {
-                            to_save = true;
+                            action = MenueAction::Logout;
                             break;
                         } // Exit the loop
                         _ => {}
@@ -390,7 +719,80 @@ pub fn display_uris(mut entries: Vec<Entry>) -> Result<(Vec<Entry>, bool), Box<d
     disable_raw_mode().unwrap();
     terminal.clear()?;
 
-    Ok((entries, to_save))
+    Ok((entries, action))
+}
+
+// Show an entry in the popup, returns true if the vault has to be locked
+fn open_entry(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    entry: &mut Entry,
+    read_only: bool,
+    lock_timeout: u64,
+) -> Result<bool, Box<dyn Error>> {
+    let mut popup = PasswordPopup::from_entry(entry);
+    popup.read_only = read_only;
+    let lock = display_popup(terminal, &mut popup, lock_timeout)?;
+    popup.finish();
+    Ok(lock)
+}
+
+// Returns true if the vault has to be locked
+fn display_popup(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    popup: &mut PasswordPopup,
+    lock_timeout: u64,
+) -> Result<bool, Box<dyn Error>> {
+    let mut idle_timer = IdleTimer::new(lock_timeout);
+    loop {
+        terminal.draw(|f| {
+            let (size, status_area) = status_area(f.area());
+            let area = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Percentage(30), Constraint::Percentage(70)])
+                .split(size);
+
+            popup.render(area[1], f.buffer_mut()); // Render the popup
+            render_status(status_area, f.buffer_mut());
+        })?;
+
+        // Redraw periodically so the TOTP and clipboard countdowns stay live
+        if !event::poll(Duration::from_millis(250))? {
+            if idle_timer.expired() {
+                return Ok(true);
+            }
+            continue;
+        }
+
+        if let event::Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                idle_timer.reset();
+                match key.code {
+                    KeyCode::Esc => return Ok(false),
+                    KeyCode::Char('l') if key.modifiers == KeyModifiers::CONTROL => {
+                        return Ok(true);
+                    }
+                    KeyCode::Char('t') if key.modifiers == KeyModifiers::CONTROL => {
+                        popup.copy_totp();
+                    }
+                    KeyCode::Char('g')
+                        if key.modifiers == KeyModifiers::CONTROL
+                            && !popup.read_only
+                            && popup.entry.item_type == ItemType::Login =>
+                    {
+                        if let Some(password) = generator::view::draw_generator(&mut idle_timer) {
+                            popup.set_password(password);
+                        }
+                        terminal.clear()?;
+                        if idle_timer.expired() {
+                            return Ok(true);
+                        }
+                    }
+                    KeyCode::Tab => popup.focus_next(),
+                    _ => popup.handle_input(key.code, key.modifiers),
+                }
+            }
+        }
+    }
 }
 
 struct StatefulList {
@@ -409,7 +811,17 @@ impl StatefulList {
         self.state.selected()
     }
 
+    // Replace the items, e.g. after filtering, and select the first one
+    fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.state
+            .select(if self.items.is_empty() { None } else { Some(0) });
+    }
+
     fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -424,6 +836,9 @@ impl StatefulList {
     }
 
     fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -444,40 +859,140 @@ impl StatefulList {
     }
 }
 
-pub fn update_error(code: i16) {
-    enable_raw_mode().unwrap();
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen).unwrap();
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend).unwrap();
+// Sidebar rows: all entries, entries without folder, then the folder tree
+fn sidebar_filters(entries: &[Entry]) -> Vec<FolderFilter> {
+    let mut filters = vec![
+        FolderFilter::All,
+        FolderFilter::Recent,
+        FolderFilter::Unfiled,
+    ];
+    filters.extend(
+        logik::folders(entries)
+            .into_iter()
+            .map(FolderFilter::Folder),
+    );
+    filters
+}
 
-    loop {
-        terminal
-            .draw(|frame| {
-                let size = frame.area();
-                let block = Block::default()
-                    .borders(Borders::ALL)
-                    .title("Error")
-                    .title_style(Style::default().add_modifier(Modifier::BOLD));
-                let paragraph = match code {
-                    401 => Paragraph::new("Logout successfull!").block(block),
-                    500 => Paragraph::new("Database Error or JWT Extraction Error!").block(block),
-                    _ => Paragraph::new("Unknown Error!").block(block),
-                };
-                frame.render_widget(paragraph, size);
-            })
-            .unwrap();
-        if let Event::Key(key_event) = event::read().unwrap() {
-            if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Enter {
-                break;
+fn sidebar_names(entries: &[Entry], filters: &[FolderFilter]) -> Vec<String> {
+    filters
+        .iter()
+        .map(|filter| {
+            let count = entries.iter().filter(|entry| filter.matches(entry)).count();
+            match filter {
+                FolderFilter::All => format!("All entries ({})", count),
+                FolderFilter::Recent => {
+                    format!("Recently used ({})", count.min(logik::RECENT_ENTRIES))
+                }
+                FolderFilter::Unfiled => format!("No folder ({})", count),
+                FolderFilter::Folder(folder) => {
+                    // Indent subfolders below their parent
+                    let depth = folder.matches('/').count();
+                    let name = folder.rsplit('/').next().unwrap_or(folder);
+                    format!("{}{} ({})", "  ".repeat(depth), name, count)
+                }
             }
-        }
+        })
+        .collect()
+}
+
+// Filtered and sorted entries, search results stay ordered by relevance
+fn visible_hits(
+    entries: &[Entry],
+    query: &str,
+    folder: &FolderFilter,
+    tag: Option<&str>,
+    sort_order: SortOrder,
+) -> Vec<logik::SearchHit> {
+    let mut hits = logik::filter_entries(entries, query, folder, tag);
+    if *folder == FolderFilter::Recent {
+        logik::sort_hits(entries, &mut hits, SortOrder::LastUsed);
+        hits.truncate(logik::RECENT_ENTRIES);
+    } else if query.is_empty() {
+        logik::sort_hits(entries, &mut hits, sort_order);
+        // Favorites stay on top in the chosen order
+        hits.sort_by_key(|hit| !entries[hit.index].favorite);
     }
-    disable_raw_mode().unwrap();
-    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+    hits
+}
+
+// The login password was found in a breach, results arrive in the background
+fn is_breached(entry: &Entry) -> bool {
+    entry.item_type == ItemType::Login
+        && !entry.login.password.is_empty()
+        && breach::logik::breach_count(&entry.login.password).is_some_and(|count| count > 0)
+}
+
+fn selected_filter(sidebar: &StatefulList, filters: &[FolderFilter]) -> FolderFilter {
+    sidebar
+        .state
+        .selected()
+        .and_then(|i| filters.get(i))
+        .cloned()
+        .unwrap_or(FolderFilter::All)
+}
+
+// Cycle through the tags of the vault, ending with no tag filter
+fn next_tag(entries: &[Entry], current: Option<&str>) -> Option<String> {
+    let tags = logik::tags(entries);
+    match current.and_then(|current| tags.iter().position(|tag| tag == current)) {
+        Some(i) => tags.get(i + 1).cloned(),
+        None => tags.first().cloned(),
+    }
+}
+
+// Complete the typed folder with the next existing folder in sidebar order
+fn next_folder(entries: &[Entry], typed: &str) -> String {
+    let folders = logik::folders(entries);
+    match folders.iter().position(|folder| folder == typed) {
+        Some(i) => folders[(i + 1) % folders.len()].clone(),
+        None => folders
+            .iter()
+            .find(|folder| folder.to_lowercase().starts_with(&typed.to_lowercase()))
+            .or(folders.first())
+            .cloned()
+            .unwrap_or_default(),
+    }
+}
+
+fn hit_names(entries: &[Entry], hits: &[logik::SearchHit]) -> Vec<String> {
+    hits.iter()
+        .map(|hit| entries[hit.index].name.clone())
+        .collect()
+}
+
+// Render a name with the fuzzy matched characters emphasized
+fn highlight_matches<'a>(name: &'a str, positions: &[usize]) -> Line<'a> {
+    if positions.is_empty() {
+        return Line::from(name);
+    }
+    let highlight = Style::default()
+        .fg(Color::Rgb(255, 163, 26))
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    Line::from(
+        name.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if positions.contains(&i) {
+                    Span::styled(c.to_string(), highlight)
+                } else {
+                    Span::raw(c.to_string())
+                }
+            })
+            .collect::<Vec<_>>(),
+    )
 }
 
-pub fn serialization_error() {
+// Show why loading or saving the vault failed
+pub fn update_error(error: &(dyn Error + 'static)) {
+    let message = match error
+        .downcast_ref::<PassHubError>()
+        .and_then(PassHubError::status)
+    {
+        Some(401) => "The session has ended. Please log in again.".to_string(),
+        _ => error.to_string(),
+    };
+
     enable_raw_mode().unwrap();
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen).unwrap();
@@ -492,8 +1007,9 @@ pub fn serialization_error() {
                     .borders(Borders::ALL)
                     .title("Error")
                     .title_style(Style::default().add_modifier(Modifier::BOLD));
-                let paragraph = Paragraph::new("Serialization Error!").block(block);
-
+                let paragraph = Paragraph::new(format!("{} \nPress Enter to continue.", message))
+                    .wrap(Wrap { trim: true })
+                    .block(block);
                 frame.render_widget(paragraph, size);
             })
             .unwrap();
@@ -503,8 +1019,6 @@ pub fn serialization_error() {
             }
         }
     }
-
-    // Restore terminal
     disable_raw_mode().unwrap();
     execute!(io::stdout(), LeaveAlternateScreen).unwrap();
 }
@@ -515,13 +1029,24 @@ struct PasswordPopup<'a> {
     _border_style: Style,
     style: Style,
     edit_mode: EditMode,
+    edit_field_name: bool, // Typing goes into the name of the selected custom field
     entry: &'a mut Entry,
+    totp: Option<Totp>,
+    read_only: bool,           // Offline vaults can only be viewed and copied from
+    previous_password: String, // Goes into the history if the password is changed
+    snapshot: String,          // The entry as JSON, to tell whether it was edited
+    copies: u64,               // Clipboard copies before the popup was opened
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum EditMode {
     None,
-    Uri,
+    Uri(usize),     // Index into the URI list
+    Field(usize),   // Index into the custom fields
+    Item(usize),    // Index into the fields of a card, identity or SSH key
+    History(usize), // Index into the replaced passwords
+    Folder,
+    Tags, // Comma separated while editing
     Password,
     Note,
     Username,
@@ -530,68 +1055,98 @@ enum EditMode {
 
 impl<'a> PasswordPopup<'a> {
     pub fn from_entry(entry: &'a mut Entry) -> Self {
+        let totp = entry.login.totp.as_deref().and_then(Totp::parse);
+        let entry_password = entry.login.password.clone();
+        entry.payload_mut(); // Vaults from other clients may omit the type data
+        let snapshot = serde_json::to_string(&*entry).unwrap_or_default();
         PasswordPopup {
             _title: Line::from("Password Entry"),
             _border_style: Style::default(),
             style: Style::default(),
             entry,
             edit_mode: EditMode::None,
+            edit_field_name: false,
+            totp,
+            read_only: false,
+            previous_password: entry_password,
+            snapshot,
+            copies: clipboard::copy_count(),
+        }
+    }
+
+    // Called when the popup is closed, keeps the replaced password in the history
+    // and updates the timestamps
+    pub fn finish(&mut self) {
+        let previous_password = std::mem::take(&mut self.previous_password);
+        self.entry.login.record_password_change(previous_password);
+        logik::normalize_folder_and_tags(self.entry);
+
+        let mut current = serde_json::to_string(&*self.entry).unwrap_or_default();
+        if current != self.snapshot {
+            self.entry.touch();
+        }
+        current.zeroize();
+        self.snapshot.zeroize();
+        if clipboard::copy_count() != self.copies {
+            self.entry.last_used = Some(Utc::now());
+        }
+    }
+
+    pub fn set_password(&mut self, password: String) {
+        self.entry.login.password.zeroize();
+        self.entry.login.password = password;
+        self.edit_mode = EditMode::Password; // Show the new password
+    }
+
+    // Tab order: every URI, then password, previous passwords, username, notes, folder, tags,
+    // every custom field and name.
+    // Other item types have their own fields in place of URIs, password and username.
+    fn focus_next(&mut self) {
+        self.edit_field_name = false;
+        let item_fields = self.entry.payload().map_or(0, |p| p.labels().len());
+        self.edit_mode = match self.edit_mode {
+            EditMode::None | EditMode::Name if self.entry.item_type != ItemType::Login => {
+                if item_fields > 0 {
+                    EditMode::Item(0)
+                } else {
+                    EditMode::Note
+                }
+            }
+            EditMode::Item(i) if i + 1 < item_fields => EditMode::Item(i + 1),
+            EditMode::Item(_) => EditMode::Note,
+            EditMode::None | EditMode::Name => EditMode::Uri(0),
+            EditMode::Uri(i) if i + 1 < self.entry.login.uris.len() => EditMode::Uri(i + 1),
+            EditMode::Uri(_) => EditMode::Password,
+            EditMode::Password if !self.entry.login.password_history.is_empty() => {
+                EditMode::History(0)
+            }
+            EditMode::History(i) if i + 1 < self.entry.login.password_history.len() => {
+                EditMode::History(i + 1)
+            }
+            EditMode::Password | EditMode::History(_) => EditMode::Username,
+            EditMode::Username => EditMode::Note,
+            EditMode::Note => EditMode::Folder,
+            EditMode::Folder => EditMode::Tags,
+            EditMode::Tags => EditMode::Field(0),
+            EditMode::Field(i) if i + 1 < self.entry.fields.len() => EditMode::Field(i + 1),
+            EditMode::Field(_) => EditMode::Name,
+        };
+    }
+
+    pub fn copy_totp(&self) {
+        if let Some(totp) = &self.totp {
+            clipboard::copy(totp.generate_now());
         }
     }
 
     pub fn render(&self, area: Rect, buf: &mut Buffer) {
         let mut content = Text::default();
         Clear.render(area, buf);
-        content.lines.push(Line::from(vec![
-            Span::raw("URI: "),
-            if matches!(self.edit_mode, EditMode::Uri) {
-                Span::styled(
-                    &self.entry.login.uris[0].uri,
-                    Style::default().fg(Color::Rgb(255, 163, 26)),
-                )
-            } else {
-                Span::raw(&self.entry.login.uris[0].uri)
-            },
-        ]));
-        content.lines.push(Line::from(vec![
-            Span::raw("Password: "),
-            if matches!(self.edit_mode, EditMode::Password) {
-                Span::styled(
-                    &self.entry.login.password,
-                    Style::default().fg(Color::Rgb(255, 163, 26)),
-                )
-            } else {
-                Span::raw("********")
-            },
-        ]));
-        content.lines.push(Line::from(vec![
-            Span::raw("Username: "),
-            if matches!(self.edit_mode, EditMode::Username) {
-                if self.entry.login.username.is_some() {
-                    Span::styled(
-                        self.entry.login.username.as_ref().unwrap(),
-                        Style::default().fg(Color::Rgb(255, 163, 26)),
-                    )
-                } else {
-                    Span::raw("(none)").style(Style::default().fg(Color::Rgb(255, 163, 26)))
-                }
-            } else if self.entry.login.username.is_some() {
-                Span::styled(
-                    self.entry.login.username.as_ref().unwrap(),
-                    Style::default(),
-                )
-            } else {
-                Span::styled("(none)", Style::default())
-            },
-        ]));
-
-        content.lines.push(Line::from(vec![
-            Span::raw("totp: "),
-            Span::styled(
-                self.entry.login.totp.as_deref().unwrap_or("(none)"),
-                Style::default(),
-            ),
-        ]));
+        if let Some(payload) = self.entry.payload() {
+            self.render_item(payload, &mut content);
+        } else {
+            self.render_login(&mut content);
+        }
         //if \n in the string to be rendered, there shall be a line break
         if let Some(notes) = self.entry.notes.as_deref() {
             let lines: Vec<&str> = notes.split('\n').collect();
@@ -621,13 +1176,126 @@ impl<'a> PasswordPopup<'a> {
             ]));
         }
 
+        let highlight = Style::default().fg(Color::Rgb(255, 163, 26));
+        let folder = self.entry.folder.as_deref().unwrap_or("(none)");
+        content.lines.push(Line::from(vec![
+            Span::raw("Folder: "),
+            if self.edit_mode == EditMode::Folder {
+                Span::styled(folder, highlight)
+            } else {
+                Span::raw(folder)
+            },
+        ]));
+        let tags = if self.entry.tags.is_empty() && self.edit_mode != EditMode::Tags {
+            "(none)".to_string()
+        } else {
+            self.entry.tags.join(", ")
+        };
+        content.lines.push(Line::from(vec![
+            Span::raw("Tags: "),
+            if self.edit_mode == EditMode::Tags {
+                Span::styled(tags, highlight)
+            } else {
+                Span::raw(tags)
+            },
+        ]));
+        if self.entry.fields.is_empty() {
+            content.lines.push(Line::from(vec![
+                Span::raw("Fields: "),
+                if matches!(self.edit_mode, EditMode::Field(_)) {
+                    Span::styled("(none)", highlight)
+                } else {
+                    Span::raw("(none)")
+                },
+            ]));
+        }
+        for (i, field) in self.entry.fields.iter().enumerate() {
+            let selected = self.edit_mode == EditMode::Field(i);
+            let name = if field.name.is_empty() && !selected {
+                "(unnamed)".to_string()
+            } else {
+                field.name.clone()
+            };
+            let value = match field.field_type {
+                FieldType::Hidden if !selected => "********".to_string(),
+                FieldType::Text | FieldType::Hidden => field.value.clone().unwrap_or_default(),
+                FieldType::Boolean if field.value.as_deref() == Some("true") => "[x]".to_string(),
+                FieldType::Boolean => "[ ]".to_string(),
+                FieldType::Linked => match field.linked_id {
+                    Some(LinkedId::Username) => "-> Username".to_string(),
+                    _ => "-> Password".to_string(),
+                },
+            };
+            let type_label = match field.field_type {
+                FieldType::Text => "text",
+                FieldType::Hidden => "hidden",
+                FieldType::Boolean => "boolean",
+                FieldType::Linked => "linked",
+            };
+            content.lines.push(Line::from(vec![
+                if selected && self.edit_field_name {
+                    Span::styled(name, highlight)
+                } else {
+                    Span::raw(name)
+                },
+                Span::raw(": "),
+                if selected && !self.edit_field_name {
+                    Span::styled(value, highlight)
+                } else {
+                    Span::raw(value)
+                },
+                Span::styled(
+                    format!(" [{}]", type_label),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+
+        let timestamp = |date: Option<DateTime<Utc>>| match date {
+            Some(date) => date
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+            None => "unknown".to_string(),
+        };
+        content.lines.push(Line::from(Span::styled(
+            format!(
+                "Created {}, modified {}, last used {}",
+                timestamp(self.entry.created),
+                timestamp(self.entry.modified),
+                match self.entry.last_used {
+                    Some(_) => timestamp(self.entry.last_used),
+                    None => "never".to_string(),
+                }
+            ),
+            Style::default().fg(Color::DarkGray),
+        )));
+
         // Render the block and paragraph
         let block = Block::default()
-            .title(Line::from("Password Entry"))
+            .title(Line::from(match (self.entry.item_type, self.read_only) {
+                (ItemType::Login, true) => "Password Entry (read-only)".to_string(),
+                (ItemType::Login, false) => "Password Entry".to_string(),
+                (item_type, true) => format!("{} (read-only)", item_type.label()),
+                (item_type, false) => item_type.label().to_string(),
+            }))
             .title_style(Style::default().add_modifier(Modifier::BOLD))
             .borders(Borders::ALL)
             .border_style(Style::default())
-            .title_bottom(Line::from("Switch between fields (TAB), Return (ESC)"));
+            .title_bottom(Line::from(match self.edit_mode {
+                EditMode::Uri(_) if !self.read_only => {
+                    "Next field (TAB), Add URI (CTRL+N), Remove URI (DEL), Move URI (ALT+arrow keys), Match type (CTRL+U), Return (ESC)"
+                }
+                EditMode::Field(_) if !self.read_only => {
+                    "Next field (TAB), Add field (CTRL+N), Remove field (DEL), Move field (ALT+arrow keys), Type (CTRL+U), Edit name/value (CTRL+R), Toggle (Space), Copy (CTRL+C), Return (ESC)"
+                }
+                _ if self.entry.item_type != ItemType::Login => {
+                    "Switch between fields (TAB), Copy (CTRL+C), Lock (CTRL+L), Return (ESC)"
+                }
+                _ => {
+                    "Switch between fields (TAB), Generate password (CTRL+G), Copy TOTP (CTRL+T), Lock (CTRL+L), Return (ESC)"
+                }
+            }));
 
         Paragraph::new(content)
             .block(block)
@@ -636,219 +1304,581 @@ impl<'a> PasswordPopup<'a> {
             .render(area, buf);
     }
 
+    // URIs, password, username and TOTP of a login
+    fn render_login<'b>(&'b self, content: &mut Text<'b>) {
+        if self.entry.login.uris.is_empty() {
+            content.lines.push(Line::from(vec![
+                Span::raw("URI: "),
+                if matches!(self.edit_mode, EditMode::Uri(_)) {
+                    Span::styled("(none)", Style::default().fg(Color::Rgb(255, 163, 26)))
+                } else {
+                    Span::raw("(none)")
+                },
+            ]));
+        }
+        for (i, uri) in self.entry.login.uris.iter().enumerate() {
+            let selected = self.edit_mode == EditMode::Uri(i);
+            content.lines.push(Line::from(vec![
+                Span::raw(format!("URI {}: ", i + 1)),
+                if selected {
+                    Span::styled(&uri.uri, Style::default().fg(Color::Rgb(255, 163, 26)))
+                } else {
+                    Span::raw(&uri.uri)
+                },
+                Span::styled(
+                    format!(" [{}]", UriMatch::label(uri.match_type)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+        content.lines.push(Line::from(vec![
+            Span::raw("Password: "),
+            if matches!(self.edit_mode, EditMode::Password) {
+                Span::styled(
+                    &self.entry.login.password,
+                    Style::default().fg(Color::Rgb(255, 163, 26)),
+                )
+            } else {
+                Span::raw("********")
+            },
+        ]));
+        for (i, old) in self.entry.login.password_history.iter().enumerate() {
+            let replaced = old
+                .last_used_date
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M");
+            content.lines.push(Line::from(vec![
+                Span::raw(format!("Previous password {}: ", i + 1)),
+                if self.edit_mode == EditMode::History(i) {
+                    Span::styled(&old.password, Style::default().fg(Color::Rgb(255, 163, 26)))
+                } else {
+                    Span::raw("********")
+                },
+                Span::styled(
+                    format!(" [replaced {}]", replaced),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+        content.lines.push(Line::from(vec![
+            Span::raw("Username: "),
+            if matches!(self.edit_mode, EditMode::Username) {
+                Span::styled(
+                    self.entry.login.username.as_deref().unwrap_or("(none)"),
+                    Style::default().fg(Color::Rgb(255, 163, 26)),
+                )
+            } else {
+                Span::styled(
+                    self.entry.login.username.as_deref().unwrap_or("(none)"),
+                    Style::default(),
+                )
+            },
+        ]));
+
+        content
+            .lines
+            .push(match (&self.totp, &self.entry.login.totp) {
+                (Some(totp), _) => {
+                    // Countdown bar shrinks as the current code approaches expiry
+                    let remaining = totp.remaining_seconds();
+                    let width = 20;
+                    let filled = (remaining * width / totp.period) as usize;
+                    let bar = format!(
+                        "{}{}",
+                        "█".repeat(filled),
+                        "░".repeat(width as usize - filled)
+                    );
+                    let bar_color = if remaining <= 5 {
+                        Color::Red
+                    } else {
+                        Color::Rgb(255, 163, 26)
+                    };
+                    Line::from(vec![
+                        Span::raw("TOTP: "),
+                        Span::styled(
+                            totp.generate_now(),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(" "),
+                        Span::styled(bar, Style::default().fg(bar_color)),
+                        Span::raw(format!(" {}s", remaining)),
+                    ])
+                }
+                (None, Some(_)) => Line::from(vec![
+                    Span::raw("TOTP: "),
+                    Span::styled("(invalid otpauth URI)", Style::default().fg(Color::Red)),
+                ]),
+                (None, None) => Line::from(vec![Span::raw("TOTP: "), Span::raw("(none)")]),
+            });
+    }
+
+    // The fields of a card, identity or SSH key, secrets are masked unless selected
+    fn render_item<'b>(&self, payload: &'b dyn ItemPayload, content: &mut Text<'b>) {
+        let highlight = Style::default().fg(Color::Rgb(255, 163, 26));
+        let values = payload.values();
+        for (i, (label, secret)) in payload.labels().iter().enumerate() {
+            let selected = self.edit_mode == EditMode::Item(i);
+            let value = match values[i].as_deref() {
+                Some(value) if !value.is_empty() => value,
+                _ => "(none)",
+            };
+            if *secret && !selected && value != "(none)" {
+                content
+                    .lines
+                    .push(Line::from(format!("{}: ********", label)));
+                continue;
+            }
+            // SSH keys span several lines
+            for (n, line) in value.split('\n').enumerate() {
+                content.lines.push(Line::from(vec![
+                    if n == 0 {
+                        Span::raw(format!("{}: ", label))
+                    } else {
+                        Span::raw(" ".repeat(label.len() + 2))
+                    },
+                    if selected {
+                        Span::styled(line, highlight)
+                    } else {
+                        Span::raw(line)
+                    },
+                ]));
+            }
+        }
+    }
+
     pub fn handle_input(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        // Only copying is allowed without a backend to save to
+        if self.read_only && (key, modifiers) != (KeyCode::Char('c'), KeyModifiers::CONTROL) {
+            return;
+        }
         match self.edit_mode {
-            EditMode::Uri => match (key, modifiers) {
+            EditMode::Uri(i) => self.handle_uri_input(i, key, modifiers),
+            EditMode::Field(i) => self.handle_field_input(i, key, modifiers),
+            EditMode::Item(i) => self.handle_item_input(i, key, modifiers),
+            // Previous passwords can only be copied
+            EditMode::History(i) => {
+                if (key, modifiers) == (KeyCode::Char('c'), KeyModifiers::CONTROL) {
+                    if let Some(old) = self.entry.login.password_history.get(i) {
+                        clipboard::copy(old.password.clone());
+                    }
+                }
+            }
+            EditMode::Password => match (key, modifiers) {
                 (
                     KeyCode::Char(c),
                     KeyModifiers::NONE | KeyModifiers::SHIFT | KeyModifiers::ALT,
-                ) => {
-                    if logik::validate_string_length(&self.entry.login.uris[0].uri) {
-                        self.entry.login.uris[0].uri.push(c);
-                    }
+                ) if logik::validate_string_length(&self.entry.login.password) => {
+                    self.entry.login.password.push(c);
                 }
                 //Alt Gr
                 (KeyCode::Char(c), m)
-                    if m.contains(KeyModifiers::ALT) && m.contains(KeyModifiers::CONTROL) =>
+                    if m.contains(KeyModifiers::ALT)
+                        && m.contains(KeyModifiers::CONTROL)
+                        && logik::validate_string_length(&self.entry.login.password) =>
                 {
-                    if logik::validate_string_length(&self.entry.login.uris[0].uri) {
-                        self.entry.login.uris[0].uri.push(c);
-                    }
+                    self.entry.login.password.push(c);
                 }
                 (KeyCode::Backspace, KeyModifiers::NONE) => {
-                    self.entry.login.uris[0].uri.pop();
+                    self.entry.login.password.pop();
                 }
-                (KeyCode::Tab, KeyModifiers::NONE) => self.edit_mode = EditMode::Password,
                 // Copy (Ctrl + C)
                 (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-                    if let Some(uri) = self.entry.login.uris.first() {
-                        let uri_clone = uri.uri.clone();
-                        copy_to_clipboard(uri_clone);
-                    }
+                    let password_clone = self.entry.login.password.clone();
+                    clipboard::copy(password_clone);
                 }
                 // Paste (Ctrl + V)
                 (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
-                    if let Some(content) = paste_from_clipboard() {
+                    if let Some(content) = clipboard::paste() {
                         if logik::validate_string_length(&content) {
-                            self.entry.login.uris[0].uri.push_str(&content);
+                            self.entry.login.password.push_str(&content);
                         }
                     }
                 }
                 _ => {}
             },
-            EditMode::Password => match (key, modifiers) {
+            EditMode::Note => match (key, modifiers) {
                 (
                     KeyCode::Char(c),
                     KeyModifiers::NONE | KeyModifiers::SHIFT | KeyModifiers::ALT,
-                ) => {
-                    if logik::validate_string_length(&self.entry.login.password) {
-                        self.entry.login.password.push(c);
-                    }
-                }
+                ) => push_optional(&mut self.entry.notes, c),
                 //Alt Gr
                 (KeyCode::Char(c), m)
                     if m.contains(KeyModifiers::ALT) && m.contains(KeyModifiers::CONTROL) =>
                 {
-                    if logik::validate_string_length(&self.entry.login.password) {
-                        self.entry.login.password.push(c);
-                    }
+                    push_optional(&mut self.entry.notes, c)
                 }
                 (KeyCode::Backspace, KeyModifiers::NONE) => {
-                    self.entry.login.password.pop();
+                    if let Some(notes) = self.entry.notes.as_mut() {
+                        notes.pop();
+                    }
+                }
+                (KeyCode::Enter, KeyModifiers::NONE) => {
+                    self.entry.notes.get_or_insert_with(String::new).push('\n');
                 }
-                (KeyCode::Tab, KeyModifiers::NONE) => self.edit_mode = EditMode::Username,
                 // Copy (Ctrl + C)
                 (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-                    let password_clone = self.entry.login.password.clone();
-                    copy_to_clipboard(password_clone);
+                    if let Some(notes) = &self.entry.notes {
+                        clipboard::copy(notes.clone());
+                    }
                 }
                 // Paste (Ctrl + V)
                 (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
-                    if let Some(content) = paste_from_clipboard() {
-                        if logik::validate_string_length(&content) {
-                            self.entry.login.password.push_str(&content);
-                        }
+                    if let Some(content) = clipboard::paste() {
+                        paste_optional(&mut self.entry.notes, content);
                     }
                 }
                 _ => {}
             },
-            EditMode::Note => match (key, modifiers) {
+            EditMode::Username => match (key, modifiers) {
                 (
                     KeyCode::Char(c),
                     KeyModifiers::NONE | KeyModifiers::SHIFT | KeyModifiers::ALT,
-                ) => {
-                    if self.entry.notes.is_some() {
-                        if logik::validate_string_length(self.entry.notes.as_ref().unwrap()) {
-                            self.entry.notes.as_mut().unwrap().push(c);
-                        }
-                    } else {
-                        self.entry.notes = Some(String::new());
-                        self.entry.notes.as_mut().unwrap().push(c);
-                    }
-                }
+                ) => push_optional(&mut self.entry.login.username, c),
                 //Alt Gr
                 (KeyCode::Char(c), m)
                     if m.contains(KeyModifiers::ALT) && m.contains(KeyModifiers::CONTROL) =>
                 {
-                    if self.entry.notes.is_some() {
-                        if logik::validate_string_length(self.entry.notes.as_ref().unwrap()) {
-                            self.entry.notes.as_mut().unwrap().push(c);
-                        }
-                    } else {
-                        self.entry.notes = Some(String::new());
-                        self.entry.notes.as_mut().unwrap().push(c);
-                    }
+                    push_optional(&mut self.entry.login.username, c)
                 }
                 (KeyCode::Backspace, KeyModifiers::NONE) => {
-                    if self.entry.notes.is_some() {
-                        self.entry.notes.as_mut().unwrap().pop();
-                    }
-                }
-                (KeyCode::Enter, KeyModifiers::NONE) => {
-                    if self.entry.notes.is_none() {
-                        self.entry.notes = Some(String::new());
+                    if let Some(username) = self.entry.login.username.as_mut() {
+                        username.pop();
                     }
-                    self.entry.notes.as_mut().unwrap().push('\n');
                 }
-                (KeyCode::Tab, KeyModifiers::NONE) => self.edit_mode = EditMode::Uri,
                 // Copy (Ctrl + C)
                 (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-                    if let Some(notes) = &self.entry.notes {
-                        copy_to_clipboard(notes.clone());
+                    if let Some(username) = &self.entry.login.username {
+                        clipboard::copy(username.clone());
                     }
                 }
                 // Paste (Ctrl + V)
                 (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
-                    if let Some(content) = paste_from_clipboard() {
-                        if self.entry.notes.is_some() {
-                            if logik::validate_string_length(&content) {
-                                self.entry.notes.as_mut().unwrap().push_str(&content);
-                            }
-                        } else {
-                            self.entry.notes = Some(content);
-                        }
+                    if let Some(content) = clipboard::paste() {
+                        paste_optional(&mut self.entry.login.username, content);
                     }
                 }
                 _ => {}
             },
-            EditMode::Username => match (key, modifiers) {
+            EditMode::Folder => match (key, modifiers) {
                 (
                     KeyCode::Char(c),
                     KeyModifiers::NONE | KeyModifiers::SHIFT | KeyModifiers::ALT,
-                ) => {
-                    if self.entry.login.username.is_some() {
-                        if logik::validate_string_length(
-                            self.entry.login.username.as_ref().unwrap(),
-                        ) {
-                            self.entry.login.username.as_mut().unwrap().push(c);
-                        }
-                    } else {
-                        self.entry.login.username = Some(String::new());
-                        self.entry.login.username.as_mut().unwrap().push(c);
+                ) => push_optional(&mut self.entry.folder, c),
+                //Alt Gr
+                (KeyCode::Char(c), m)
+                    if m.contains(KeyModifiers::ALT) && m.contains(KeyModifiers::CONTROL) =>
+                {
+                    push_optional(&mut self.entry.folder, c)
+                }
+                (KeyCode::Backspace, KeyModifiers::NONE) => {
+                    if let Some(folder) = self.entry.folder.as_mut() {
+                        folder.pop();
                     }
                 }
+                _ => {}
+            },
+            EditMode::Tags => match (key, modifiers) {
+                // A comma starts the next tag
+                (KeyCode::Char(','), _) => self.entry.tags.push(String::new()),
+                (
+                    KeyCode::Char(c),
+                    KeyModifiers::NONE | KeyModifiers::SHIFT | KeyModifiers::ALT,
+                ) => push_tag(&mut self.entry.tags, c),
                 //Alt Gr
                 (KeyCode::Char(c), m)
                     if m.contains(KeyModifiers::ALT) && m.contains(KeyModifiers::CONTROL) =>
                 {
-                    if self.entry.login.username.is_some() {
-                        if logik::validate_string_length(
-                            self.entry.login.username.as_ref().unwrap(),
-                        ) {
-                            self.entry.login.username.as_mut().unwrap().push(c);
+                    push_tag(&mut self.entry.tags, c)
+                }
+                // Deleting past the start of a tag removes it
+                (KeyCode::Backspace, KeyModifiers::NONE) => match self.entry.tags.last_mut() {
+                    Some(tag) if !tag.is_empty() => {
+                        tag.pop();
+                    }
+                    Some(_) => {
+                        self.entry.tags.pop();
+                    }
+                    None => {}
+                },
+                _ => {}
+            },
+            EditMode::None => {}
+            EditMode::Name => {}
+        }
+    }
+
+    // Edit the URI at index i, the list can also be extended, shortened and reordered here
+    fn handle_uri_input(&mut self, i: usize, key: KeyCode, modifiers: KeyModifiers) {
+        let uris = &mut self.entry.login.uris;
+        match (key, modifiers) {
+            // Add URI (Ctrl + N) below the selected one
+            (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                let index = if uris.is_empty() { 0 } else { i + 1 };
+                uris.insert(index, Uri::new(String::new()));
+                self.edit_mode = EditMode::Uri(index);
+            }
+            // Remove URI
+            (KeyCode::Delete, _) if i < uris.len() => {
+                uris.remove(i);
+                self.edit_mode = EditMode::Uri(i.min(uris.len().saturating_sub(1)));
+            }
+            // Move URI (Alt + Up / Alt + Down)
+            (KeyCode::Up, KeyModifiers::ALT) if i > 0 && i < uris.len() => {
+                uris.swap(i, i - 1);
+                self.edit_mode = EditMode::Uri(i - 1);
+            }
+            (KeyCode::Down, KeyModifiers::ALT) if i + 1 < uris.len() => {
+                uris.swap(i, i + 1);
+                self.edit_mode = EditMode::Uri(i + 1);
+            }
+            // Cycle the match type (Ctrl + U)
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                if let Some(uri) = uris.get_mut(i) {
+                    uri.match_type = UriMatch::next(uri.match_type);
+                }
+            }
+            // Copy (Ctrl + C)
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                if let Some(uri) = uris.get(i) {
+                    clipboard::copy(uri.uri.clone());
+                }
+            }
+            // Paste (Ctrl + V)
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
+                if let Some(content) = clipboard::paste() {
+                    if logik::validate_string_length(&content) {
+                        uri_at(uris, i).uri.push_str(&content);
+                    }
+                }
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT | KeyModifiers::ALT) => {
+                push_uri(uri_at(uris, i), c)
+            }
+            //Alt Gr
+            (KeyCode::Char(c), m)
+                if m.contains(KeyModifiers::ALT) && m.contains(KeyModifiers::CONTROL) =>
+            {
+                push_uri(uri_at(uris, i), c)
+            }
+            (KeyCode::Backspace, KeyModifiers::NONE) => {
+                if let Some(uri) = uris.get_mut(i) {
+                    uri.uri.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Edit the custom field at index i, Ctrl+R switches between its name and value
+    fn handle_field_input(&mut self, i: usize, key: KeyCode, modifiers: KeyModifiers) {
+        let fields = &mut self.entry.fields;
+        match (key, modifiers) {
+            // Add field (Ctrl + N) below the selected one, starting with its name
+            (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                let index = if fields.is_empty() { 0 } else { i + 1 };
+                fields.insert(index, Field::default());
+                self.edit_mode = EditMode::Field(index);
+                self.edit_field_name = true;
+            }
+            // Remove field
+            (KeyCode::Delete, _) if i < fields.len() => {
+                fields[i].zeroize();
+                fields.remove(i);
+                self.edit_mode = EditMode::Field(i.min(fields.len().saturating_sub(1)));
+            }
+            // Move field (Alt + Up / Alt + Down)
+            (KeyCode::Up, KeyModifiers::ALT) if i > 0 && i < fields.len() => {
+                fields.swap(i, i - 1);
+                self.edit_mode = EditMode::Field(i - 1);
+            }
+            (KeyCode::Down, KeyModifiers::ALT) if i + 1 < fields.len() => {
+                fields.swap(i, i + 1);
+                self.edit_mode = EditMode::Field(i + 1);
+            }
+            _ if i >= fields.len() => {} // Nothing to edit until a field is added
+            // Cycle the type (Ctrl + U)
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => fields[i].cycle_type(),
+            // Switch between name and value (Ctrl + R)
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                self.edit_field_name = !self.edit_field_name;
+            }
+            // Copy (Ctrl + C), linked fields copy the value they refer to
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                if let Some(value) = fields[i].resolve(&self.entry.login) {
+                    clipboard::copy(value);
+                }
+            }
+            // Paste (Ctrl + V)
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
+                if let Some(content) = clipboard::paste() {
+                    if let Some(text) = field_text(&mut fields[i], self.edit_field_name) {
+                        if logik::validate_string_length(&content) {
+                            text.push_str(&content);
                         }
-                    } else {
-                        self.entry.login.username = Some(String::new());
-                        self.entry.login.username.as_mut().unwrap().push(c);
                     }
                 }
-                (KeyCode::Backspace, KeyModifiers::NONE) => {
-                    if self.entry.login.username.is_some() {
-                        self.entry.login.username.as_mut().unwrap().pop();
+            }
+            (KeyCode::Char(' '), KeyModifiers::NONE)
+                if !self.edit_field_name
+                    && matches!(fields[i].field_type, FieldType::Boolean | FieldType::Linked) =>
+            {
+                fields[i].toggle()
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT | KeyModifiers::ALT) => {
+                if let Some(text) = field_text(&mut fields[i], self.edit_field_name) {
+                    if logik::validate_string_length(text) {
+                        text.push(c);
                     }
                 }
-                (KeyCode::Tab, KeyModifiers::NONE) => self.edit_mode = EditMode::Note,
-                // Copy (Ctrl + C)
-                (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-                    if let Some(username) = &self.entry.login.username {
-                        copy_to_clipboard(username.clone());
+            }
+            //Alt Gr
+            (KeyCode::Char(c), m)
+                if m.contains(KeyModifiers::ALT) && m.contains(KeyModifiers::CONTROL) =>
+            {
+                if let Some(text) = field_text(&mut fields[i], self.edit_field_name) {
+                    if logik::validate_string_length(text) {
+                        text.push(c);
                     }
                 }
-                // Paste (Ctrl + V)
-                (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
-                    if let Some(content) = paste_from_clipboard() {
-                        if self.entry.login.username.is_some() {
-                            if logik::validate_string_length(&content) {
-                                self.entry
-                                    .login
-                                    .username
-                                    .as_mut()
-                                    .unwrap()
-                                    .push_str(&content);
-                            }
-                        } else {
-                            self.entry.login.username = Some(content);
-                        }
+            }
+            (KeyCode::Backspace, KeyModifiers::NONE) => {
+                if let Some(text) = field_text(&mut fields[i], self.edit_field_name) {
+                    text.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Edit field i of a card, identity or SSH key
+    fn handle_item_input(&mut self, i: usize, key: KeyCode, modifiers: KeyModifiers) {
+        let ssh_key = self.entry.item_type == ItemType::SshKey;
+        let Some(value) = self
+            .entry
+            .payload_mut()
+            .and_then(|payload| payload.values_mut().into_iter().nth(i))
+        else {
+            return;
+        };
+        match (key, modifiers) {
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT | KeyModifiers::ALT) => {
+                push_optional(value, c)
+            }
+            //Alt Gr
+            (KeyCode::Char(c), m)
+                if m.contains(KeyModifiers::ALT) && m.contains(KeyModifiers::CONTROL) =>
+            {
+                push_optional(value, c)
+            }
+            (KeyCode::Backspace, KeyModifiers::NONE) => {
+                if let Some(value) = value.as_mut() {
+                    value.pop();
+                }
+            }
+            // SSH keys span several lines
+            (KeyCode::Enter, KeyModifiers::NONE) if ssh_key => {
+                value.get_or_insert_with(String::new).push('\n');
+            }
+            // Copy (Ctrl + C)
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                if let Some(value) = value {
+                    clipboard::copy(value.clone());
+                }
+            }
+            // Paste (Ctrl + V), keys are longer than the length limit of other fields
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
+                if let Some(content) = clipboard::paste() {
+                    if ssh_key {
+                        value.get_or_insert_with(String::new).push_str(&content);
+                    } else {
+                        paste_optional(value, content);
                     }
                 }
-                _ => {}
-            },
-            EditMode::None => {}
-            EditMode::Name => {}
+            }
+            _ => {}
+        }
+    }
+}
+
+// The editable text of a custom field, booleans and linked fields only have a name
+fn field_text(field: &mut Field, name: bool) -> Option<&mut String> {
+    match (name, field.field_type) {
+        (true, _) => Some(&mut field.name),
+        (false, FieldType::Text | FieldType::Hidden) => {
+            Some(field.value.get_or_insert_with(String::new))
         }
+        (false, _) => None,
+    }
+}
+
+// The URI at index i, typing into an empty list creates the first URI
+fn uri_at(uris: &mut Vec<Uri>, i: usize) -> &mut Uri {
+    if uris.is_empty() {
+        uris.push(Uri::new(String::new()));
     }
+    let last = uris.len() - 1;
+    &mut uris[i.min(last)]
 }
 
-fn copy_to_clipboard(content: String) {
-    let mut clipboard = ClipboardContext::new().unwrap();
-    clipboard.set_contents(content).unwrap();
+fn push_uri(uri: &mut Uri, c: char) {
+    if logik::validate_string_length(&uri.uri) {
+        uri.uri.push(c);
+    }
 }
 
-fn paste_from_clipboard() -> Option<String> {
-    if let Ok(mut clipboard) = ClipboardContext::new() {
-        if let Ok(content) = clipboard.get_contents() {
-            return Some(content);
-        }
+// Append a character to the last tag, creating it on first input
+fn push_tag(tags: &mut Vec<String>, c: char) {
+    match tags.last_mut() {
+        Some(tag) if logik::validate_string_length(tag) => tag.push(c),
+        Some(_) => {}
+        None => tags.push(c.to_string()),
+    }
+}
+
+// Append a character to an optional field, creating it on first input
+fn push_optional(field: &mut Option<String>, c: char) {
+    match field {
+        Some(value) if logik::validate_string_length(value) => value.push(c),
+        Some(_) => {}
+        None => *field = Some(c.to_string()),
+    }
+}
+
+// Append pasted content to an optional field, creating it if empty
+fn paste_optional(field: &mut Option<String>, content: String) {
+    match field {
+        Some(value) if logik::validate_string_length(&content) => value.push_str(&content),
+        Some(_) => {}
+        None => *field = Some(content),
+    }
+}
+
+// One line at the bottom of the screen for the clipboard countdown and the session
+// expiry warning, None if there is nothing to show
+fn status_message() -> Option<String> {
+    let clipboard = clipboard::remaining_seconds()
+        .map(|seconds| format!("Clipboard is cleared in {}s", seconds));
+    match (clipboard, session::logik::warning()) {
+        (Some(clipboard), Some(session)) => Some(format!("{} | {}", clipboard, session)),
+        (clipboard, session) => clipboard.or(session),
+    }
+}
+
+fn status_area(area: Rect) -> (Rect, Option<Rect>) {
+    if status_message().is_none() {
+        return (area, None);
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(area);
+    (chunks[0], Some(chunks[1]))
+}
+
+fn render_status(area: Option<Rect>, buf: &mut Buffer) {
+    if let (Some(area), Some(message)) = (area, status_message()) {
+        Paragraph::new(message)
+            .style(Style::default().fg(Color::Rgb(255, 163, 26)))
+            .render(area, buf);
     }
-    None
 }
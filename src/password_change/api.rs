@@ -0,0 +1,31 @@
+use crate::client::RsPassClient;
+use crate::error::Result;
+use crate::login::kdf::KdfParams;
+use reqwest::header::IF_MATCH;
+use serde_json::{json, Value};
+
+// Replace password hash, vault and KDF parameters in one request, so the backend can swap them atomically.
+// With a revision the backend rejects the change with 409 or 412 if the vault changed since.
+pub fn change_password(
+    client: &RsPassClient,
+    password_hash: &str,
+    encrypted_data: &str,
+    kdf: &KdfParams,
+    revision: Option<&str>,
+) -> Result<()> {
+    let mut payload = json!({
+        "password_hash": password_hash,
+        "encrypted_data": encrypted_data
+    });
+    if !kdf.is_legacy() {
+        payload["kdf"] = serde_json::to_value(kdf)?;
+    }
+    let mut request = client.post("/api/v1/account/changepassword");
+    if let Some(revision) = revision {
+        payload["revision"] = Value::String(revision.to_string());
+        request = request.header(IF_MATCH, format!("\"{}\"", revision));
+    }
+
+    client.send(request.json(&payload))?;
+    Ok(())
+}
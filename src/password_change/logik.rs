@@ -0,0 +1,153 @@
+use super::{api, view};
+use crate::client::RsPassClient;
+use crate::error::PassHubError;
+use crate::lock::logik::IdleTimer;
+use crate::login::kdf::{self, KdfParams};
+use crate::login::logik::{self as login, LoginError};
+use crate::menue::logik::zeroize_json;
+use crate::menue::{api as vault_api, cache};
+use crate::sync::logik::CONFLICT_CODES;
+use serde_json::Value;
+use zeroize::Zeroize;
+
+// Give up if other devices keep saving while the password is changed
+const MAX_ATTEMPTS: usize = 3;
+
+#[derive(Debug)]
+pub enum ChangeError {
+    Argon2,
+    WrongPassword,
+    Api(PassHubError),
+}
+
+// Returns true if the master password was changed and the user has to log in again.
+// Gives up once idle_timer expires, the caller has to lock the vault then.
+pub fn change_master_password(client: &RsPassClient, idle_timer: &mut IdleTimer) -> bool {
+    let email = login::get_mail_from_storage();
+    let (mut old_password, mut new_password) = match view::draw_change_password_screen(idle_timer) {
+        Some(passwords) => passwords,
+        None => return false, // Cancelled or locked
+    };
+
+    let result = change(client, &email, &old_password, &new_password);
+    old_password.zeroize(); // Clear plaintext passwords from memory
+    new_password.zeroize();
+
+    match result {
+        Ok(()) => {
+            view::success();
+            true
+        }
+        Err(ChangeError::Argon2) => {
+            view::error_argon2_fail();
+            false
+        }
+        Err(ChangeError::WrongPassword) => {
+            view::error_wrong_password();
+            false
+        }
+        Err(ChangeError::Api(e)) => {
+            view::error(&e.to_string());
+            false
+        }
+    }
+}
+
+fn change(
+    client: &RsPassClient,
+    email: &str,
+    old_password: &str,
+    new_password: &str,
+) -> Result<(), ChangeError> {
+    // Verify the current password by logging in with it, which also yields a fresh session.
+    // Without the renewable session, a wrong password must not ask to log in again.
+    let (session, mut old_key) =
+        login::login_with_credentials(&client.without_token(), email, old_password).map_err(
+            |e| match e {
+                LoginError::Argon2 => ChangeError::Argon2,
+                LoginError::Backend(e) if matches!(e.status(), Some(401 | 404)) => {
+                    ChangeError::WrongPassword
+                }
+                LoginError::Backend(e) => ChangeError::Api(e),
+            },
+        )?;
+
+    // A new password gets a new salt, unless the backend only knows e-mail salts
+    let params = if kdf::load(email).is_legacy() {
+        KdfParams::legacy()
+    } else {
+        KdfParams::random()
+    };
+    let result = rekey(&session, email, &old_key, new_password, &params);
+    old_key.zeroize();
+    result?.zeroize();
+
+    kdf::save(email, &params);
+    _ = vault_api::logout(&session);
+    Ok(())
+}
+
+// Re-encrypt the vault under the keys derived from password and params and replace
+// password hash, vault and KDF parameters on the backend. Returns the new master key.
+pub fn rekey(
+    session: &RsPassClient,
+    email: &str,
+    old_key: &str,
+    new_password: &str,
+    params: &KdfParams,
+) -> Result<String, ChangeError> {
+    let (mut new_key, new_password_hash) =
+        login::derive_keys(email, new_password, params).map_err(|_| ChangeError::Argon2)?;
+
+    match replace_vault(session, old_key, &new_key, &new_password_hash, params) {
+        Ok(encrypted_data) => {
+            cache::save(&encrypted_data);
+            Ok(new_key)
+        }
+        Err(e) => {
+            new_key.zeroize();
+            Err(ChangeError::Api(e))
+        }
+    }
+}
+
+// Send the vault re-encrypted under the new key together with the new password hash.
+// If another device saved in between, the vault is fetched again so its changes are kept.
+fn replace_vault(
+    session: &RsPassClient,
+    old_key: &str,
+    new_key: &str,
+    new_password_hash: &str,
+    params: &KdfParams,
+) -> Result<String, PassHubError> {
+    for _ in 0..MAX_ATTEMPTS {
+        // Decrypt the vault with the old key
+        let remote = vault_api::fetch_encrypted(session)?;
+        let revision = remote.as_ref().and_then(|remote| remote.revision.clone());
+        let mut vault = match remote {
+            Some(remote) => vault_api::decrypt_vault(&remote.encrypted_data, old_key)?,
+            None => None,
+        }
+        .unwrap_or(Value::Array(Vec::new()));
+
+        let encrypted_data = vault_api::encrypt_vault(&vault, new_key);
+        zeroize_json(&mut vault);
+        let encrypted_data = encrypted_data?;
+
+        // All are replaced in one request, so the vault always matches the accepted password
+        match api::change_password(
+            session,
+            new_password_hash,
+            &encrypted_data,
+            params,
+            revision.as_deref(),
+        ) {
+            Ok(()) => return Ok(encrypted_data),
+            Err(e)
+                if e.status()
+                    .is_some_and(|status| CONFLICT_CODES.contains(&status)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Err(PassHubError::Conflict)
+}
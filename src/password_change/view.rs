@@ -0,0 +1,214 @@
+use crate::lock::logik::IdleTimer;
+use crate::register::logik::validate_password;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Terminal,
+};
+use std::io::{self, stdout};
+use zeroize::Zeroize;
+
+// Returns (current password, new password), None if the user pressed ESC or idle_timer expired
+pub fn draw_change_password_screen(idle_timer: &mut IdleTimer) -> Option<(String, String)> {
+    // Initialize terminal
+    enable_raw_mode().unwrap();
+    let stdout = stdout();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.clear().unwrap();
+
+    let labels = ["Current password", "New password", "Repeat new password"];
+    let mut fields = [String::new(), String::new(), String::new()];
+    let mut focus = 0;
+    let mut error_message = String::new();
+
+    let result = loop {
+        terminal
+            .draw(|f| {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .margin(2)
+                    .constraints(
+                        [
+                            Constraint::Percentage(25),
+                            Constraint::Percentage(15),
+                            Constraint::Percentage(15),
+                            Constraint::Percentage(15),
+                            Constraint::Percentage(30),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(f.area());
+
+                // Title
+                let title = Paragraph::new("Change Master Password")
+                    .style(
+                        Style::default()
+                            .fg(Color::Rgb(255, 163, 26))
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .alignment(Alignment::Center)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Master Password")
+                            .title_bottom("Next field (Enter), cancel (ESC)"),
+                    );
+                f.render_widget(title, chunks[0]);
+
+                // Password inputs
+                for (i, label) in labels.iter().enumerate() {
+                    let password_masked: String = "*".repeat(fields[i].len());
+                    let paragraph = Paragraph::new(format!("{}: {}", label, password_masked))
+                        .style(Style::default().fg(Color::White))
+                        .block(Block::default().borders(Borders::ALL).title(if focus == i {
+                            *label
+                        } else {
+                            " "
+                        }));
+                    f.render_widget(paragraph, chunks[i + 1]);
+                }
+
+                // Error message
+                let error_paragraph = Paragraph::new(error_message.clone())
+                    .style(Style::default().fg(Color::Red))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true });
+                f.render_widget(error_paragraph, chunks[4]);
+            })
+            .unwrap();
+
+        // Read inputs
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => {
+                fields.iter_mut().for_each(|field| field.zeroize());
+                break None;
+            }
+        };
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press {
+                match key.code {
+                    KeyCode::Enter if focus < 2 => focus += 1,
+                    KeyCode::Enter => {
+                        if !validate_password(&fields[1]) {
+                            error_message = String::from(
+                                "Invalid password: Must be at least 10 characters long and include uppercase letters, lowercase letters, numbers, and special characters.",
+                            );
+                            fields[1].clear();
+                            fields[2].clear();
+                            focus = 1;
+                        } else if fields[1] != fields[2] {
+                            error_message = String::from("The new passwords do not match.");
+                            fields[2].clear();
+                        } else if fields[0] == fields[1] {
+                            error_message =
+                                String::from("The new password must differ from the current one.");
+                            fields[1].clear();
+                            fields[2].clear();
+                            focus = 1;
+                        } else {
+                            let [current, new, mut repeated] = fields;
+                            repeated.zeroize();
+                            break Some((current, new));
+                        }
+                    }
+                    KeyCode::Esc => {
+                        fields.iter_mut().for_each(|field| field.zeroize());
+                        break None;
+                    }
+                    KeyCode::Backspace => {
+                        fields[focus].pop();
+                    }
+                    KeyCode::Char(c) => fields[focus].push(c),
+                    KeyCode::Up => focus = focus.saturating_sub(1),
+                    KeyCode::Down => focus = (focus + 1).min(2),
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    // Clean up terminal
+    terminal.clear().unwrap();
+    disable_raw_mode().unwrap();
+    execute!(
+        terminal.backend_mut(),
+        crossterm::terminal::LeaveAlternateScreen
+    )
+    .unwrap();
+
+    result
+}
+
+pub fn success() {
+    message_screen(
+        "Success",
+        "Your master password has been changed. \nPress Enter to log in with the new password.",
+    );
+}
+
+pub fn error_wrong_password() {
+    message_screen(
+        "Error",
+        "The current password is wrong. Nothing has been changed. \nPress Enter to return.",
+    );
+}
+
+pub fn error(message: &str) {
+    message_screen(
+        "Error",
+        &format!(
+            "{} Nothing has been changed. \nPress Enter to return.",
+            message
+        ),
+    );
+}
+
+pub fn error_argon2_fail() {
+    message_screen(
+        "Error",
+        "Argon 2 password hashing failed. Nothing has been changed. \nPress Enter to return.",
+    );
+}
+
+fn message_screen(title: &str, message: &str) {
+    // Setup terminal for message screen
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    loop {
+        terminal
+            .draw(|frame| {
+                let size = frame.area();
+                let block = Block::default().borders(Borders::ALL).title(title);
+
+                let paragraph = Paragraph::new(message).block(block);
+
+                frame.render_widget(paragraph, size);
+            })
+            .unwrap();
+
+        // Wait for user input to dismiss the screen
+        if let Event::Key(key_event) = event::read().unwrap() {
+            if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Enter {
+                break;
+            }
+        }
+    }
+
+    // Restore terminal
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+}
@@ -1,31 +1,31 @@
-use reqwest::blocking::Client;
+use crate::client::RsPassClient;
+use crate::error::{PassHubError, Result};
+use crate::login::kdf::KdfParams;
 use serde_json::json;
 
-pub fn login_backend(base_url: &str, email: &str, password_hash: &str) -> Result<String, u16> {
-    let client = Client::new();
-    let url = format!("{}/api/v1/auth/register", base_url); // Combine base URL with API endpoint
-
-    let payload = json!({
+// The KDF parameters are only sent to backends with per-account salts
+pub fn login_backend(
+    client: &RsPassClient,
+    email: &str,
+    password_hash: &str,
+    kdf: &KdfParams,
+) -> Result<String> {
+    let mut payload = json!({
         "email": email,
         "password_hash": password_hash
     });
+    if !kdf.is_legacy() {
+        payload["kdf"] = serde_json::to_value(kdf)?;
+    }
 
-    let response = client.post(&url).json(&payload).send();
+    let response = client.send(client.post("/api/v1/auth/register").json(&payload))?;
 
-    match response {
-        Ok(res) => {
-            if res.status().is_success() {
-                // Success: Extract JSON token
-                let body: serde_json::Value = res.json().unwrap_or_else(|_| json!({}));
-                if let Some(token) = body.get("token").and_then(|t| t.as_str()) {
-                    return Ok(token.to_string());
-                }
-                Err(500) // If no token is present, internal server error
-            } else {
-                // Return the HTTP status code on errors
-                Err(res.status().as_u16())
-            }
-        }
-        Err(_) => Err(500), // Network error or other problem
+    // Extract the JSON token
+    let body: serde_json::Value = response.json()?;
+    match body.get("token").and_then(|t| t.as_str()) {
+        Some(token) => Ok(token.to_string()),
+        None => Err(PassHubError::Serialization(
+            "The backend response contains no token".to_string(),
+        )),
     }
 }
@@ -1,52 +1,55 @@
 use super::{api, view};
-use argon2::{
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Algorithm, Argon2, Params, Version,
-};
+use crate::client::RsPassClient;
+use crate::login::kdf::{self, KdfParams};
+use crate::login::{api as login_api, logik as login};
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::{fs, io::Write};
 use zeroize::Zeroize;
 
-pub fn register(backend_url: &str) -> (String, String) {
+// Returns the logged in client and the master key
+pub fn register(client: &RsPassClient) -> (RsPassClient, String) {
     loop {
         // Prompt for username and password
         let (email, mut cleartext_password) = view::draw_register_screen();
 
-        // Hash the password
-        match hash_argon_2_master_key(&cleartext_password, &email) {
-            Ok(mut master_key) => {
-                let master_password_hash =
-                    hash_argon_2_master_password_hash(&master_key, &cleartext_password);
-                cleartext_password.zeroize(); // Clear plaintext password from memory
+        // New accounts get a random salt if the backend supports per-account salts
+        let params = match login_api::prelogin(client, &email) {
+            Ok(Some(_)) => KdfParams::random(),
+            Ok(None) => KdfParams::legacy(),
+            // The backend has a prelogin endpoint, but does not know the account yet
+            Err(e) if e.status() == Some(404) => KdfParams::random(),
+            Err(e) => {
+                cleartext_password.zeroize();
+                view::error(&e.to_string());
+                continue;
+            }
+        };
 
+        // Hash the password
+        let keys = login::derive_keys(&email, &cleartext_password, &params);
+        cleartext_password.zeroize(); // Clear plaintext password from memory
+        match keys {
+            Ok((mut master_key, master_password_hash)) => {
                 // Proceed with backend login
-                match api::login_backend(
-                    backend_url,
-                    &email,
-                    &master_password_hash
-                        .expect("Register: Error occurred extracting master password"),
-                ) {
+                match api::login_backend(client, &email, &master_password_hash, &params) {
                     Ok(token) => {
                         save_email_to_storage(&email); // Save email
-                        return (token, master_key); // Return JWT token
+                        kdf::save(&email, &params);
+                        return (client.with_token(&token), master_key); // Client with the JWT token
                     }
-                    Err(status) => {
-                        match status {
-                            400 => view::error_bad_request(), // Bad request
-                            409 => {
-                                master_key.zeroize();
-                                view::error_user_exists();
-                            } // Already exists
-                            500 => view::error_network(),     // Internal server error
-                            _ => view::error_unknown(),       // Unknown error
+                    Err(e) => {
+                        master_key.zeroize();
+                        match e.status() {
+                            Some(400) => view::error_bad_request(), // Bad request
+                            Some(409) => view::error_user_exists(), // Already exists
+                            _ => view::error(&e.to_string()),
                         }
                     }
                 }
             }
             Err(_e) => {
-                cleartext_password.clear(); // Clear plaintext password from memory
                 view::error_argon2_fail();
                 std::process::exit(1);
             }
@@ -54,42 +57,6 @@ pub fn register(backend_url: &str) -> (String, String) {
     }
 }
 
-fn hash_argon_2_master_key(
-    password: &str,
-    email: &str,
-) -> Result<String, argon2::password_hash::Error> {
-    let salt = SaltString::encode_b64(email.as_bytes())?;
-    let params = Params::new(65536, 3, 4, None)?; // 64 MiB, 3 iterations, 4 lane/thread
-    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
-
-    // Hash the password
-    let password_hash = argon2
-        .hash_password(password.as_bytes(), &salt)?
-        .to_string();
-
-    // Verify the hash to ensure correctness
-    let parsed_hash = PasswordHash::new(&password_hash)?;
-    argon2.verify_password(password.as_bytes(), &parsed_hash)?;
-
-    Ok(password_hash)
-}
-
-fn hash_argon_2_master_password_hash(
-    master_key: &str,
-    master_password: &str,
-) -> Result<String, argon2::password_hash::Error> {
-    let salt = SaltString::encode_b64(master_password.as_bytes())?;
-    let params = Params::new(65536, 3, 4, None)?; // 64 MiB, 3 iterations, 4 lane/thread
-    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
-
-    // Hash the password
-    let password_hash = argon2
-        .hash_password(master_key.as_bytes(), &salt)?
-        .to_string();
-
-    Ok(password_hash)
-}
-
 fn save_email_to_storage(email: &str) {
     if let Some(proj_dirs) = ProjectDirs::from("dev", "passhub", "passhub") {
         let config_dir = proj_dirs.config_dir();
@@ -8,7 +8,7 @@ use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Wrap},
     Terminal,
 };
 use std::io::{self, stdout};
@@ -176,7 +176,8 @@ pub fn error_argon2_fail() {
     execute!(io::stdout(), LeaveAlternateScreen).unwrap();
 }
 
-pub fn error_network() {
+// Show why the request failed
+pub fn error(message: &str) {
     // Setup terminal for error screen
     enable_raw_mode().unwrap();
     let mut stdout = io::stdout();
@@ -190,8 +191,9 @@ pub fn error_network() {
                 let size = frame.area();
                 let block = Block::default().borders(Borders::ALL).title("Error");
 
-                let paragraph =
-                    Paragraph::new("Network Error. \n Press Enter to try again.").block(block);
+                let paragraph = Paragraph::new(format!("{} \nPress Enter to try again.", message))
+                    .wrap(Wrap { trim: true })
+                    .block(block);
 
                 frame.render_widget(paragraph, size);
             })
@@ -244,40 +246,6 @@ pub fn error_bad_request() {
     execute!(io::stdout(), LeaveAlternateScreen).unwrap();
 }
 
-pub fn error_unknown() {
-    // Setup terminal for error screen
-    enable_raw_mode().unwrap();
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen).unwrap();
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend).unwrap();
-
-    loop {
-        terminal
-            .draw(|frame| {
-                let size = frame.area();
-                let block = Block::default().borders(Borders::ALL).title("Error");
-
-                let paragraph =
-                    Paragraph::new("Unknown Error. \n Press Enter to try again.").block(block);
-
-                frame.render_widget(paragraph, size);
-            })
-            .unwrap();
-
-        // Wait for user input to dismiss the error screen
-        if let Event::Key(key_event) = event::read().unwrap() {
-            if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Enter {
-                break;
-            }
-        }
-    }
-
-    // Restore terminal
-    disable_raw_mode().unwrap();
-    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
-}
-
 pub fn error_user_exists() {
     // Setup terminal for error screen
     enable_raw_mode().unwrap();
@@ -0,0 +1,87 @@
+use crate::client::{self, RsPassClient};
+use crate::lock::logik::{IdleTimer, Verifier};
+use crate::lock::view as lock_view;
+use crate::login::{api as login_api, kdf, logik as login};
+use crate::settings;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::sync::Mutex;
+use zeroize::Zeroize;
+
+// The JWT token expires after a while. Instead of logging out, PassHub asks for
+// the master password again and sends the rejected request once more.
+
+// Warn this long before the session token expires
+const WARN_BEFORE: TimeDelta = TimeDelta::minutes(5);
+
+// Expiry of the session shown in the entry list
+static EXPIRES: Mutex<Option<DateTime<Utc>>> = Mutex::new(None);
+
+// The session of the client, renewed with the master password when it expires.
+// The verifier makes sure the password still derives the key the vault is encrypted with.
+pub fn renewable(client: &RsPassClient, verifier: Verifier) -> RsPassClient {
+    *EXPIRES.lock().unwrap() = client.expires_at();
+    let login_client = client.without_token();
+    client.on_expiry(move || reauthenticate(&login_client, &verifier))
+}
+
+// Stop showing the expiry warning after logout
+pub fn forget() {
+    *EXPIRES.lock().unwrap() = None;
+}
+
+// Warning for the status line, None while the session is far from expiring
+pub fn warning() -> Option<String> {
+    let expires = (*EXPIRES.lock().unwrap())?;
+    let remaining = expires - Utc::now();
+    if remaining > WARN_BEFORE {
+        None
+    } else if remaining > TimeDelta::zero() {
+        Some(format!(
+            "Session expires in {}:{:02}, saving then asks for your master password",
+            remaining.num_minutes(),
+            remaining.num_seconds() % 60
+        ))
+    } else {
+        Some("Session expired, saving asks for your master password".to_string())
+    }
+}
+
+// Log in again with the master password, returns the new token.
+// None if the user cancelled, the rejected request then fails as before.
+fn reauthenticate(client: &RsPassClient, verifier: &Verifier) -> Option<String> {
+    let email = login::get_mail_from_storage();
+    let mut error: Option<String> = None;
+    // The screen that sent the request started its timer earlier, so once this one
+    // expires the request fails with that timer expired as well and the vault is locked
+    let mut idle_timer = IdleTimer::new(settings::logik::load().lock_timeout_seconds);
+
+    loop {
+        let mut password =
+            lock_view::draw_session_screen(&email, error.as_deref(), &mut idle_timer)?;
+        let keys = login::derive_keys(&email, &password, &kdf::load(&email));
+        password.zeroize();
+
+        let (mut master_key, master_password_hash) = match keys {
+            Ok(keys) => keys,
+            Err(_) => {
+                lock_view::error_argon2_fail();
+                return None;
+            }
+        };
+        // Checked locally first, a password changed on another device would not decrypt the vault
+        let matches = verifier.matches(&master_key);
+        master_key.zeroize();
+        if !matches {
+            error = Some("Wrong master password.".to_string());
+            continue;
+        }
+
+        match login_api::login_backend(client, &email, &master_password_hash) {
+            Ok(token) => {
+                *EXPIRES.lock().unwrap() = client::token_expiry(&token);
+                return Some(token);
+            }
+            Err(e) => error = Some(e.to_string()),
+        }
+    }
+}
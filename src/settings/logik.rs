@@ -0,0 +1,84 @@
+use crate::menue::logik::SortOrder;
+use directories::ProjectDirs;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// User settings, read from settings.json in the config directory.
+// Missing keys keep their default, a timeout of 0 disables the feature.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub clipboard_timeout_seconds: u64,
+    pub lock_timeout_seconds: u64,
+    pub sort_order: SortOrder,        // Changed with s in the entry list
+    pub password_max_age_days: u64,   // Older passwords are listed in the health report
+    pub hibp_file: Option<PathBuf>,   // Sorted Have I Been Pwned SHA-1 file for offline checks
+    pub hibp_api_url: Option<String>, // Range API used when no file is set
+    pub connect_timeout_seconds: u64,
+    pub request_timeout_seconds: u64,
+    pub retry_attempts: u32, // Tries when the backend cannot be reached
+    pub ca_bundle: Option<PathBuf>, // PEM file with extra CA certificates
+    pub pinned_certificate: Option<PathBuf>, // PEM certificate, the only one trusted, excludes ca_bundle
+    pub proxy_url: Option<String>, // Proxy for all requests, otherwise HTTPS_PROXY etc. apply
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            clipboard_timeout_seconds: 30,
+            lock_timeout_seconds: 300,
+            sort_order: SortOrder::default(),
+            password_max_age_days: 365,
+            hibp_file: None,
+            hibp_api_url: None,
+            connect_timeout_seconds: 10,
+            request_timeout_seconds: 30,
+            retry_attempts: 3,
+            ca_bundle: None,
+            pinned_certificate: None,
+            proxy_url: None,
+        }
+    }
+}
+
+fn settings_file() -> Option<PathBuf> {
+    ProjectDirs::from("dev", "passhub", "passhub")
+        .map(|proj_dirs| proj_dirs.config_dir().join("settings.json"))
+}
+
+pub fn load() -> Settings {
+    let settings_file = match settings_file() {
+        Some(settings_file) => settings_file,
+        None => return Settings::default(),
+    };
+
+    match fs::read_to_string(&settings_file) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            debug!("Settings: Error parsing settings.json: {}", e);
+            Settings::default()
+        }),
+        Err(_) => Settings::default(),
+    }
+}
+
+// Remember the sort order chosen in the entry list
+pub fn save_sort_order(sort_order: SortOrder) {
+    let settings_file = match settings_file() {
+        Some(settings_file) => settings_file,
+        None => return,
+    };
+    let settings = Settings {
+        sort_order,
+        ..load()
+    };
+
+    let result = fs::create_dir_all(settings_file.parent().unwrap())
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_string_pretty(&settings).map_err(|e| e.to_string()))
+        .and_then(|json| fs::write(&settings_file, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        debug!("Settings: Error writing settings.json: {}", e);
+    }
+}
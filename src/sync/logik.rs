@@ -0,0 +1,388 @@
+use crate::client::RsPassClient;
+use crate::error::{PassHubError, Result};
+use crate::menue::api::{self, Revision};
+use crate::menue::logik::{deserialize_json, generate_id, zeroize_json, Entry};
+use serde_json::Value;
+use std::collections::HashMap;
+use zeroize::Zeroize;
+
+// Status codes the backend answers with if the vault changed since it was fetched
+pub const CONFLICT_CODES: [u16; 2] = [409, 412];
+// Give up if other devices keep saving while we merge
+const MAX_ATTEMPTS: usize = 3;
+
+// How to resolve an entry that was changed here and on another device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    Mine,
+    Theirs,
+    Both, // Keep the other version and a copy of ours
+}
+
+// An entry that was changed on both sides, None if that side deleted it
+pub struct Conflict<'a> {
+    pub mine: Option<&'a Entry>,
+    pub theirs: Option<&'a Entry>,
+}
+
+// Upload the entries, merging in changes saved elsewhere since base was fetched.
+// Entries and revision are updated to what was saved.
+pub fn save(
+    client: &RsPassClient,
+    master_key: &str,
+    mut base: Vec<Entry>,
+    entries: &mut Vec<Entry>,
+    revision: &mut Revision,
+    resolve: &mut dyn FnMut(&Conflict) -> Option<Resolution>,
+) -> Result<()> {
+    let result = save_merging(client, master_key, &mut base, entries, revision, resolve);
+    // The merge base is a decrypted copy of the vault
+    base.zeroize();
+    result
+}
+
+fn save_merging(
+    client: &RsPassClient,
+    master_key: &str,
+    base: &mut Vec<Entry>,
+    entries: &mut Vec<Entry>,
+    revision: &mut Revision,
+    resolve: &mut dyn FnMut(&Conflict) -> Option<Resolution>,
+) -> Result<()> {
+    for _ in 0..MAX_ATTEMPTS {
+        let mut json_data = serde_json::to_value(&*entries)?;
+        let result = api::update(client, master_key, &json_data, revision.as_deref());
+        zeroize_json(&mut json_data);
+        match result {
+            Ok(new_revision) => {
+                *revision = new_revision;
+                return Ok(());
+            }
+            Err(e)
+                if e.status()
+                    .is_some_and(|status| CONFLICT_CODES.contains(&status)) => {}
+            Err(e) => return Err(e),
+        }
+
+        // Another device saved in the meantime, merge its changes into ours
+        let mut theirs = match api::fetch_encrypted(client)? {
+            Some(remote) => {
+                *revision = remote.revision;
+                match api::decrypt_vault(&remote.encrypted_data, master_key)? {
+                    Some(mut json_data) => {
+                        let theirs = deserialize_json(&json_data);
+                        zeroize_json(&mut json_data);
+                        theirs.map_err(|e| PassHubError::CorruptVault(e.to_string()))?
+                    }
+                    None => Vec::new(),
+                }
+            }
+            None => Vec::new(),
+        };
+        let merged = merge(base, entries, &theirs, resolve);
+        base.zeroize();
+        match merged {
+            Some(merged) => {
+                entries.zeroize();
+                *entries = merged;
+            }
+            None => {
+                theirs.zeroize();
+                return Err(PassHubError::Conflict);
+            }
+        }
+        *base = theirs;
+    }
+    Err(PassHubError::Conflict)
+}
+
+// Three-way merge by entry id. Changes made on one side only are taken over,
+// resolve is asked for entries changed on both sides. None if resolve gave up.
+pub fn merge(
+    base: &[Entry],
+    mine: &[Entry],
+    theirs: &[Entry],
+    resolve: &mut dyn FnMut(&Conflict) -> Option<Resolution>,
+) -> Option<Vec<Entry>> {
+    let mut base_values: HashMap<&str, Value> = base
+        .iter()
+        .map(|entry| (entry.id.as_str(), comparable(entry)))
+        .collect();
+    let mine_by_id: HashMap<&str, &Entry> = mine
+        .iter()
+        .map(|entry| (entry.id.as_str(), entry))
+        .collect();
+    let theirs_by_id: HashMap<&str, &Entry> = theirs
+        .iter()
+        .map(|entry| (entry.id.as_str(), entry))
+        .collect();
+
+    // Their order first, then entries only we have
+    let mut ids: Vec<&str> = theirs.iter().map(|entry| entry.id.as_str()).collect();
+    ids.extend(
+        mine.iter()
+            .map(|entry| entry.id.as_str())
+            .filter(|id| !theirs_by_id.contains_key(id)),
+    );
+
+    let mut merged = Vec::new();
+    for id in ids {
+        let mine = mine_by_id.get(id).copied();
+        let theirs = theirs_by_id.get(id).copied();
+        let base = base_values.get(id);
+        let mine_value = mine.map(comparable);
+        let theirs_value = theirs.map(comparable);
+
+        let resolution = if mine_value == theirs_value || theirs_value.as_ref() == base {
+            Some(Resolution::Mine)
+        } else if mine_value.as_ref() == base {
+            Some(Resolution::Theirs)
+        } else {
+            resolve(&Conflict { mine, theirs })
+        };
+        // The comparable copies hold the secrets in clear text
+        mine_value
+            .into_iter()
+            .chain(theirs_value)
+            .for_each(|mut value| zeroize_json(&mut value));
+        let resolution = match resolution {
+            Some(resolution) => resolution,
+            None => {
+                base_values.values_mut().for_each(zeroize_json);
+                return None;
+            }
+        };
+
+        match (resolution, mine, theirs) {
+            (Resolution::Mine, Some(mine), theirs) => merged.push(with_last_used(mine, theirs)),
+            (Resolution::Theirs, mine, Some(theirs)) => merged.push(with_last_used(theirs, mine)),
+            (Resolution::Both, Some(mine), Some(theirs)) => {
+                merged.push(theirs.clone());
+                merged.push(Entry {
+                    id: generate_id(),
+                    ..mine.clone()
+                });
+            }
+            // Both only differs from the other choices if neither side deleted the entry
+            (Resolution::Both, mine, theirs) => merged.extend(mine.or(theirs).cloned()),
+            _ => {} // The chosen side deleted the entry
+        }
+    }
+    base_values.values_mut().for_each(zeroize_json);
+    Some(merged)
+}
+
+// Copying from an entry only updates when it was last used, that alone is no conflict
+fn comparable(entry: &Entry) -> Value {
+    let mut value = serde_json::to_value(entry).unwrap_or_default();
+    if let Some(object) = value.as_object_mut() {
+        object.remove("lastUsedDate");
+    }
+    value
+}
+
+// Keep the later of both last used dates
+fn with_last_used(entry: &Entry, other: Option<&Entry>) -> Entry {
+    let mut entry = entry.clone();
+    entry.last_used = entry.last_used.max(other.and_then(|other| other.last_used));
+    entry
+}
+
+// Names of the fields that differ between both versions, for the conflict prompt.
+// The modification date is left out, it differs in every conflict.
+pub fn changed_fields(mine: &Entry, theirs: &Entry) -> Vec<String> {
+    fn diff(mine: &Value, theirs: &Value, prefix: &str, changed: &mut Vec<String>) {
+        let (Some(mine), Some(theirs)) = (mine.as_object(), theirs.as_object()) else {
+            return;
+        };
+        let mut keys: Vec<&String> = mine.keys().chain(theirs.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys.into_iter().filter(|key| *key != "revisionDate") {
+            match (mine.get(key), theirs.get(key)) {
+                (Some(a), Some(b)) if a.is_object() && b.is_object() => {
+                    diff(a, b, &format!("{}{}.", prefix, key), changed)
+                }
+                (a, b) if a != b => changed.push(format!("{}{}", prefix, key)),
+                _ => {}
+            }
+        }
+    }
+
+    let mut changed = Vec::new();
+    diff(&comparable(mine), &comparable(theirs), "", &mut changed);
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::login::kdf::KdfParams;
+    use crate::menue::logik::ItemType;
+    use crate::testing;
+    use std::slice;
+
+    fn entry(name: &str) -> Entry {
+        Entry {
+            name: name.to_string(),
+            ..Entry::new(ItemType::Login)
+        }
+    }
+
+    fn renamed(entry: &Entry, name: &str) -> Entry {
+        Entry {
+            name: name.to_string(),
+            ..entry.clone()
+        }
+    }
+
+    fn names(entries: &[Entry]) -> Vec<&str> {
+        let mut names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    // Merge without conflicts, resolve must not be asked
+    fn merge_clean(base: &[Entry], mine: &[Entry], theirs: &[Entry]) -> Vec<Entry> {
+        merge(base, mine, theirs, &mut |_| panic!("unexpected conflict")).unwrap()
+    }
+
+    #[test]
+    fn edits_on_one_side_are_taken_over() {
+        let (a, b) = (entry("a"), entry("b"));
+        let base = [a.clone(), b.clone()];
+        let mine = [renamed(&a, "a mine"), b.clone()];
+        let theirs = [a.clone(), renamed(&b, "b theirs")];
+        let merged = merge_clean(&base, &mine, &theirs);
+        assert_eq!(names(&merged), ["a mine", "b theirs"]);
+    }
+
+    #[test]
+    fn entries_added_on_either_side_are_kept() {
+        let a = entry("a");
+        let base = [a.clone()];
+        let mine = [a.clone(), entry("mine")];
+        let theirs = [a.clone(), entry("theirs")];
+        let merged = merge_clean(&base, &mine, &theirs);
+        assert_eq!(names(&merged), ["a", "mine", "theirs"]);
+    }
+
+    #[test]
+    fn deleting_an_unchanged_entry_wins() {
+        let (a, b) = (entry("a"), entry("b"));
+        let base = [a.clone(), b.clone()];
+        assert_eq!(
+            names(&merge_clean(&base, slice::from_ref(&b), &base)),
+            ["b"]
+        );
+        assert_eq!(
+            names(&merge_clean(&base, &base, slice::from_ref(&a))),
+            ["a"]
+        );
+    }
+
+    #[test]
+    fn deleting_an_edited_entry_is_a_conflict() {
+        let a = entry("a");
+        let base = [a.clone()];
+        let theirs = [renamed(&a, "a theirs")];
+        let mut asked = 0;
+        let merged = merge(&base, &[], &theirs, &mut |conflict| {
+            asked += 1;
+            assert!(conflict.mine.is_none());
+            assert_eq!(conflict.theirs.unwrap().name, "a theirs");
+            Some(Resolution::Mine)
+        });
+        assert_eq!(asked, 1);
+        assert!(merged.unwrap().is_empty());
+
+        let merged = merge(&base, &[], &theirs, &mut |_| Some(Resolution::Theirs));
+        assert_eq!(names(&merged.unwrap()), ["a theirs"]);
+    }
+
+    #[test]
+    fn edits_on_both_sides_are_resolved() {
+        let a = entry("a");
+        let base = [a.clone()];
+        let mine = [renamed(&a, "a mine")];
+        let theirs = [renamed(&a, "a theirs")];
+        let resolved =
+            |resolution| merge(&base, &mine, &theirs, &mut |_| Some(resolution)).unwrap();
+
+        assert_eq!(names(&resolved(Resolution::Mine)), ["a mine"]);
+        assert_eq!(names(&resolved(Resolution::Theirs)), ["a theirs"]);
+        let both = resolved(Resolution::Both);
+        assert_eq!(names(&both), ["a mine", "a theirs"]);
+        assert_ne!(both[0].id, both[1].id);
+    }
+
+    #[test]
+    fn entries_missing_from_the_base_are_compared_directly() {
+        let a = entry("a");
+        // Added on both sides with the same id, e.g. by an import on two devices
+        assert_eq!(
+            names(&merge_clean(&[], slice::from_ref(&a), slice::from_ref(&a))),
+            ["a"]
+        );
+
+        let mut asked = false;
+        let merged = merge(
+            &[],
+            slice::from_ref(&a),
+            &[renamed(&a, "a theirs")],
+            &mut |_| {
+                asked = true;
+                Some(Resolution::Theirs)
+            },
+        );
+        assert!(asked);
+        assert_eq!(names(&merged.unwrap()), ["a theirs"]);
+    }
+
+    #[test]
+    fn giving_up_on_a_conflict_aborts_the_merge() {
+        let a = entry("a");
+        let merged = merge(
+            slice::from_ref(&a),
+            &[renamed(&a, "a mine")],
+            &[renamed(&a, "a theirs")],
+            &mut |_| None,
+        );
+        assert!(merged.is_none());
+    }
+
+    #[test]
+    fn save_merges_after_a_rejected_revision() {
+        let (client, _) = testing::mock(None, true);
+        let (session, master_key) =
+            testing::register(&client, "sync@example.com", &KdfParams::random());
+        let a = entry("a");
+        let base = vec![a.clone()];
+        let json = serde_json::to_value(&base).unwrap();
+        let mut revision = api::update(&session, &master_key, &json, None).unwrap();
+        assert!(revision.is_some());
+
+        // Another device saves first
+        let theirs = serde_json::to_value([a.clone(), entry("theirs")]).unwrap();
+        api::update(&session, &master_key, &theirs, revision.as_deref()).unwrap();
+
+        let mut entries = vec![renamed(&a, "a mine")];
+        let loaded = revision.clone();
+        save(
+            &session,
+            &master_key,
+            base,
+            &mut entries,
+            &mut revision,
+            &mut |_| panic!("unexpected conflict"),
+        )
+        .unwrap();
+        assert_eq!(names(&entries), ["a mine", "theirs"]);
+        assert_ne!(revision, loaded);
+
+        let (stored, stored_revision) = api::fetch(&session, &master_key).unwrap();
+        let stored = deserialize_json(&stored.unwrap()).unwrap();
+        assert_eq!(names(&stored), ["a mine", "theirs"]);
+        assert_eq!(stored_revision, revision);
+    }
+}
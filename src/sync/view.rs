@@ -0,0 +1,99 @@
+use super::logik::{self, Conflict, Resolution};
+use crate::lock::logik::IdleTimer;
+use crate::menue::logik::Entry;
+use chrono::Local;
+use crossterm::{
+    event::{Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Terminal,
+};
+use std::io;
+
+// Ask which version of an entry changed on both sides to keep.
+// None if the vault has to be locked, idle_timer tells.
+pub fn resolve_conflict(conflict: &Conflict, idle_timer: &mut IdleTimer) -> Option<Resolution> {
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    let name = conflict
+        .mine
+        .or(conflict.theirs)
+        .map(|entry| entry.name.as_str())
+        .unwrap_or_default();
+    let mut text = format!(
+        "'{}' was changed on this device and on another device since the vault was loaded.\n\nThis device: {}\nOther device: {}",
+        name,
+        describe(conflict.mine),
+        describe(conflict.theirs)
+    );
+    let both = conflict.mine.is_some() && conflict.theirs.is_some();
+    if let (Some(mine), Some(theirs)) = (conflict.mine, conflict.theirs) {
+        text.push_str(&format!(
+            "\nDifferent fields: {}",
+            logik::changed_fields(mine, theirs).join(", ")
+        ));
+    }
+
+    let result = loop {
+        terminal
+            .draw(|frame| {
+                let paragraph = Paragraph::new(text.as_str())
+                    .wrap(Wrap { trim: false })
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_style(Style::default().fg(Color::Rgb(255, 163, 26)))
+                            .title("Sync Conflict")
+                            .title_style(Style::default().add_modifier(Modifier::BOLD))
+                            .title_bottom(if both {
+                                "Keep mine (m), keep theirs (t), keep both (b)"
+                            } else {
+                                "Keep mine (m), keep theirs (t)"
+                            }),
+                    );
+                frame.render_widget(paragraph, frame.area());
+            })
+            .unwrap();
+
+        let event = match idle_timer.next_event() {
+            Some(event) => event,
+            None => break None,
+        };
+        if let Event::Key(key_event) = event {
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Char('m') => break Some(Resolution::Mine),
+                    KeyCode::Char('t') => break Some(Resolution::Theirs),
+                    KeyCode::Char('b') if both => break Some(Resolution::Both),
+                    _ => {}
+                }
+            }
+        }
+    };
+
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+    result
+}
+
+fn describe(entry: Option<&Entry>) -> String {
+    match entry {
+        None => "deleted the entry".to_string(),
+        Some(entry) => match entry.modified {
+            Some(modified) => format!(
+                "changed it, last modified {}",
+                modified.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            ),
+            None => "changed it".to_string(),
+        },
+    }
+}
@@ -0,0 +1,50 @@
+use crate::client::RsPassClient;
+use crate::login::kdf::KdfParams;
+use crate::login::logik::derive_keys;
+use crate::register::api as register_api;
+use crate::settings::logik::Settings;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, Once};
+use tiny_http::Server;
+
+// Tests run against the mock backend from the examples
+#[allow(dead_code)]
+#[path = "../examples/rspass_mock.rs"]
+pub mod rspass_mock;
+
+pub const PASSWORD: &str = "Correct-Horse-9";
+
+// Tests that read or write the config, data or cache directory hold this guard.
+// HOME and XDG_* point to a temporary home and are only changed while it is held.
+pub fn isolated() -> MutexGuard<'static, ()> {
+    static HOME: Once = Once::new();
+    static LOCK: Mutex<()> = Mutex::new(());
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    HOME.call_once(|| {
+        let home = tempfile::tempdir().unwrap().keep();
+        for var in ["HOME", "XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_CACHE_HOME"] {
+            std::env::set_var(var, &home);
+        }
+    });
+    guard
+}
+
+// Start a mock backend on a free port, accounts are kept in state if given
+pub fn mock(state: Option<&Path>, prelogin: bool) -> (RsPassClient, String) {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+    let state = state.map(|state| state.display().to_string());
+    let backend = rspass_mock::Backend::new(state, prelogin, 3600);
+    std::thread::spawn(move || rspass_mock::serve(server, backend));
+
+    let url = format!("http://127.0.0.1:{}", port);
+    let client = RsPassClient::with_settings(&url, &Settings::default()).unwrap();
+    (client, url)
+}
+
+// Register with PASSWORD, returns the session and the master key
+pub fn register(client: &RsPassClient, email: &str, params: &KdfParams) -> (RsPassClient, String) {
+    let (master_key, hash) = derive_keys(email, PASSWORD, params).unwrap();
+    let token = register_api::login_backend(client, email, &hash, params).unwrap();
+    (client.with_token(&token), master_key)
+}
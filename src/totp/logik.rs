@@ -0,0 +1,238 @@
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+use zeroize::Zeroize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+#[derive(Debug, Clone)]
+pub struct Totp {
+    secret: Vec<u8>,
+    pub digits: u32,
+    pub period: u64,
+    pub algorithm: Algorithm,
+}
+
+impl Drop for Totp {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl Totp {
+    // Parse an otpauth:// URI or a bare Base32 secret as stored in Login.totp
+    pub fn parse(totp: &str) -> Option<Totp> {
+        let totp = totp.trim();
+        if !totp.to_lowercase().starts_with("otpauth://") {
+            return Some(Totp {
+                secret: decode_base32(totp)?,
+                digits: 6,
+                period: 30,
+                algorithm: Algorithm::Sha1,
+            });
+        }
+
+        let url = Url::parse(totp).ok()?;
+        if url.host_str() != Some("totp") {
+            return None; // Only time based codes are supported
+        }
+
+        let mut secret = None;
+        let mut digits = 6;
+        let mut period = 30;
+        let mut algorithm = Algorithm::Sha1;
+        for (key, value) in url.query_pairs() {
+            match key.to_lowercase().as_str() {
+                "secret" => secret = decode_base32(&value),
+                "digits" => digits = value.parse().ok()?,
+                "period" => period = value.parse().ok()?,
+                "algorithm" => {
+                    algorithm = match value.to_uppercase().as_str() {
+                        "SHA1" => Algorithm::Sha1,
+                        "SHA256" => Algorithm::Sha256,
+                        "SHA512" => Algorithm::Sha512,
+                        _ => return None,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // RFC 4226 requires at least 6 digits, more than 10 do not fit into u32
+        if !(6..=10).contains(&digits) || period == 0 {
+            return None;
+        }
+
+        Some(Totp {
+            secret: secret?,
+            digits,
+            period,
+            algorithm,
+        })
+    }
+
+    // Generate the code for the given unix timestamp (RFC 6238)
+    pub fn generate(&self, timestamp: u64) -> String {
+        let counter = (timestamp / self.period).to_be_bytes();
+        let digest = match self.algorithm {
+            Algorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(&self.secret, &counter),
+            Algorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(&self.secret, &counter),
+            Algorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(&self.secret, &counter),
+        };
+
+        // Dynamic truncation (RFC 4226, section 5.3)
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        let code = binary as u64 % 10u64.pow(self.digits);
+
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+
+    pub fn generate_now(&self) -> String {
+        self.generate(unix_time())
+    }
+
+    // Seconds until the current code expires
+    pub fn remaining_seconds(&self) -> u64 {
+        self.period - unix_time() % self.period
+    }
+}
+
+fn hmac_digest<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("TOTP: HMAC accepts keys of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// Decode RFC 4648 Base32, ignoring case, spaces and padding
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut output = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in input.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        // The cast below would map other characters onto the alphabet
+        if !c.is_ascii() {
+            return None;
+        }
+        let value = ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_uppercase() as u8)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if output.is_empty() {
+        None
+    } else {
+        Some(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors of RFC 6238, appendix B
+    const TIMES: [u64; 6] = [
+        59,
+        1111111109,
+        1111111111,
+        1234567890,
+        2000000000,
+        20000000000,
+    ];
+
+    fn rfc_totp(secret: &[u8], algorithm: Algorithm) -> Totp {
+        Totp {
+            secret: secret.to_vec(),
+            digits: 8,
+            period: 30,
+            algorithm,
+        }
+    }
+
+    fn codes(totp: &Totp) -> Vec<String> {
+        TIMES.iter().map(|time| totp.generate(*time)).collect()
+    }
+
+    #[test]
+    fn rfc_6238_sha1() {
+        let totp = rfc_totp(b"12345678901234567890", Algorithm::Sha1);
+        assert_eq!(
+            codes(&totp),
+            ["94287082", "07081804", "14050471", "89005924", "69279037", "65353130"]
+        );
+    }
+
+    #[test]
+    fn rfc_6238_sha256() {
+        let totp = rfc_totp(b"12345678901234567890123456789012", Algorithm::Sha256);
+        assert_eq!(
+            codes(&totp),
+            ["46119246", "68084774", "67062674", "91819424", "90698825", "77737706"]
+        );
+    }
+
+    #[test]
+    fn rfc_6238_sha512() {
+        let totp = rfc_totp(
+            b"1234567890123456789012345678901234567890123456789012345678901234",
+            Algorithm::Sha512,
+        );
+        assert_eq!(
+            codes(&totp),
+            ["90693936", "25091201", "99943326", "93441116", "38618901", "47863826"]
+        );
+    }
+
+    #[test]
+    fn otpauth_uri_parameters() {
+        let totp = Totp::parse(
+            "otpauth://totp/Example:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&algorithm=SHA1&digits=8&period=30",
+        )
+        .unwrap();
+        assert_eq!(totp.generate(59), "94287082");
+        assert!(Totp::parse("otpauth://hotp/Example?secret=GEZDGNBV&counter=1").is_none());
+        assert!(Totp::parse("otpauth://totp/Example?secret=GEZDGNBV&digits=5").is_none());
+    }
+
+    #[test]
+    fn base32_ignores_case_padding_and_whitespace() {
+        assert_eq!(decode_base32("MZXW6YQ=").unwrap(), b"foob");
+        assert_eq!(decode_base32("mzxw 6ytb\toi======").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn invalid_secrets_are_rejected() {
+        assert!(decode_base32("").is_none());
+        assert!(decode_base32("MZXW1").is_none()); // 1 is not in the alphabet
+        assert!(decode_base32("MZXW\u{141}").is_none()); // Ł would be cast to A
+        assert!(Totp::parse("not a secret!").is_none());
+    }
+}
@@ -1,5 +1,7 @@
 use super::api;
 use super::view;
+use crate::client::RsPassClient;
+use crate::menue::cache;
 
 use directories::ProjectDirs;
 use std::fs;
@@ -7,14 +9,20 @@ use std::io::Read;
 use std::io::Write;
 use url::Url;
 
-pub fn get_backend_url() -> String {
+// Returns None if the backend is unreachable and the user chose to open the offline cache
+pub fn get_client() -> Option<RsPassClient> {
     let mut backend_url = is_url_in_storage();
 
     //Check availability of stored URL
     if let Some(ref url) = backend_url {
-        if !api::check_health(url) {
-            view::error_url_unreachable(&Some(url.clone()));
-            backend_url = None; //Set to None to force new input
+        match connect(url) {
+            Some(client) => return Some(client),
+            None => {
+                if view::error_url_unreachable(&Some(url.clone()), cache::exists()) {
+                    return None;
+                }
+                backend_url = None; //Set to None to force new input
+            }
         }
     }
 
@@ -35,18 +43,33 @@ pub fn get_backend_url() -> String {
 
         //Check if entered URL is reachable
         if let Some(ref url) = backend_url {
-            if api::check_health(url) {
-                return url.clone(); //URL is reachable
-            } else {
-                view::error_url_unreachable(&Some(url.clone()));
-                backend_url = None; //Set to None to force new input
+            match connect(url) {
+                Some(client) => return Some(client), //URL is reachable
+                None => {
+                    if view::error_url_unreachable(&Some(url.clone()), cache::exists()) {
+                        return None;
+                    }
+                    backend_url = None; //Set to None to force new input
+                }
             }
         }
     }
 }
 
+// Client for the URL if the backend answers, quits if the connection settings are broken
+fn connect(url: &str) -> Option<RsPassClient> {
+    let client = match RsPassClient::new(url) {
+        Ok(client) => client,
+        Err(e) => {
+            view::error(&format!("{} Please check settings.json.", e));
+            std::process::exit(1);
+        }
+    };
+    api::check_health(&client).then_some(client)
+}
+
 //Returns the URL if it is stored in the file
-fn is_url_in_storage() -> Option<String> {
+pub fn is_url_in_storage() -> Option<String> {
     //Get the project directory
     if let Some(proj_dirs) = ProjectDirs::from("dev", "passhub", "passhub") {
         let config_dir = proj_dirs.config_dir();
@@ -70,7 +93,7 @@ fn is_url_in_storage() -> Option<String> {
 }
 
 //Creates the file if it does not exist
-fn create_file() {
+pub fn create_file() {
     if let Some(proj_dirs) = ProjectDirs::from("dev", "passhub", "passhub") {
         let config_dir = proj_dirs.config_dir();
         let config_file = config_dir.join("config.txt");
@@ -81,7 +104,7 @@ fn create_file() {
 }
 
 //Save the URL to the file if it is valid
-fn save_backend_url(url: &str) {
+pub fn save_backend_url(url: &str) {
     if let Some(proj_dirs) = ProjectDirs::from("dev", "passhub", "passhub") {
         let config_dir = proj_dirs.config_dir();
         let config_file = config_dir.join("config.txt");
@@ -6,7 +6,7 @@ use crossterm::{
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Wrap},
     Terminal,
 };
 use std::io::{self};
@@ -114,7 +114,43 @@ pub fn error_url_unavailable() {
     execute!(io::stdout(), LeaveAlternateScreen).unwrap();
 }
 
-pub fn error_url_unreachable(backend_url: &Option<String>) {
+pub fn error(message: &str) {
+    // Setup terminal for error screen
+    enable_raw_mode().unwrap();
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    loop {
+        terminal
+            .draw(|frame| {
+                let size = frame.area();
+                let block = Block::default().borders(Borders::ALL).title("Error");
+
+                let paragraph = Paragraph::new(format!("{}\nPress Enter to quit.", message))
+                    .wrap(Wrap { trim: false })
+                    .block(block);
+
+                frame.render_widget(paragraph, size);
+            })
+            .unwrap();
+
+        // Wait for user input to dismiss the error screen
+        if let Event::Key(key_event) = event::read().unwrap() {
+            if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Enter {
+                break;
+            }
+        }
+    }
+
+    // Restore terminal
+    disable_raw_mode().unwrap();
+    execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+}
+
+// Returns true if the user wants to open the offline cache instead
+pub fn error_url_unreachable(backend_url: &Option<String>, offline_available: bool) -> bool {
     // Setup terminal for error screen
     enable_raw_mode().unwrap();
     let mut stdout = io::stdout();
@@ -124,6 +160,7 @@ pub fn error_url_unreachable(backend_url: &Option<String>) {
 
     // Get the backend URL or use a placeholder if None
     let url_display = backend_url.as_deref().unwrap_or("Unknown");
+    let open_offline;
 
     loop {
         terminal
@@ -131,10 +168,13 @@ pub fn error_url_unreachable(backend_url: &Option<String>) {
                 let size = frame.area();
                 let block = Block::default().borders(Borders::ALL).title("Error");
 
-                let message = format!(
+                let mut message = format!(
                     "Backend on {} not reachable. Please press Enter and insert a valid URL.",
                     url_display
                 );
+                if offline_available {
+                    message.push_str("\nPress 'o' to open your cached vault offline (read-only).");
+                }
                 let paragraph = Paragraph::new(message).block(block);
 
                 frame.render_widget(paragraph, size);
@@ -143,8 +183,18 @@ pub fn error_url_unreachable(backend_url: &Option<String>) {
 
         // Wait for user input to dismiss the error screen
         if let Event::Key(key_event) = event::read().unwrap() {
-            if key_event.kind == KeyEventKind::Press && key_event.code == KeyCode::Enter {
-                break;
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Enter => {
+                        open_offline = false;
+                        break;
+                    }
+                    KeyCode::Char('o') if offline_available => {
+                        open_offline = true;
+                        break;
+                    }
+                    _ => {}
+                }
             }
         }
     }
@@ -152,4 +202,6 @@ pub fn error_url_unreachable(backend_url: &Option<String>) {
     // Restore terminal
     disable_raw_mode().unwrap();
     execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+
+    open_offline
 }